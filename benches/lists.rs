@@ -0,0 +1,265 @@
+//! Compares the six tutorial lists, `first` through `sixth`, plus `VecDeque` and std's
+//! `LinkedList`, on push/pop throughput, iteration, dropping a long list, and heap bytes per
+//! element. The crate's other collections aren't covered.
+//!
+//! Run with `cargo bench --bench lists`, optionally followed by `-- <elements>`. The report is
+//! CSV on stdout, one row per list; times are nanoseconds per element. `first` and `fourth`
+//! have no borrowing iterator, so their `iterate_ns` is left empty.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::{LinkedList as StdLinkedList, VecDeque};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use linked_list::{fifth, first, fourth, second, sixth, third};
+
+// Tracks the bytes currently allocated, so filling a list shows what each element costs
+struct CountingAllocator;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const DEFAULT_ELEMENTS: usize = 1_000_000;
+const ITERATIONS: usize = 10;
+
+/// One list under test, driven as a stack or a queue depending on what it supports.
+trait Subject: Default {
+    const NAME: &'static str;
+
+    fn push(&mut self, element: u64);
+    fn pop(&mut self) -> Option<u64>;
+    /// Sums the elements through a borrowing iterator, if the list has one.
+    fn sum(&self) -> Option<u64>;
+}
+
+impl Subject for first::List<u64> {
+    const NAME: &'static str = "first";
+
+    fn push(&mut self, element: u64) {
+        first::List::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        first::List::pop(self)
+    }
+
+    fn sum(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Subject for second::List<u64> {
+    const NAME: &'static str = "second";
+
+    fn push(&mut self, element: u64) {
+        second::List::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        second::List::pop(self)
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+impl Subject for third::List<u64> {
+    const NAME: &'static str = "third";
+
+    fn push(&mut self, element: u64) {
+        *self = self.prepend(element);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        let head = self.iter().next().copied();
+        *self = self.tail();
+        head
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+impl Subject for fourth::List<u64> {
+    const NAME: &'static str = "fourth";
+
+    fn push(&mut self, element: u64) {
+        self.push_tail(element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        self.pop_head()
+    }
+
+    fn sum(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Subject for fifth::List<u64> {
+    const NAME: &'static str = "fifth";
+
+    fn push(&mut self, element: u64) {
+        fifth::List::push(self, element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        fifth::List::pop(self)
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+impl Subject for sixth::LinkedList<u64> {
+    const NAME: &'static str = "sixth";
+
+    fn push(&mut self, element: u64) {
+        self.push_back(element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        self.pop_front()
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+impl Subject for VecDeque<u64> {
+    const NAME: &'static str = "std_vecdeque";
+
+    fn push(&mut self, element: u64) {
+        self.push_back(element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        self.pop_front()
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+impl Subject for StdLinkedList<u64> {
+    const NAME: &'static str = "std_linkedlist";
+
+    fn push(&mut self, element: u64) {
+        self.push_back(element)
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        self.pop_front()
+    }
+
+    fn sum(&self) -> Option<u64> {
+        Some(self.iter().sum())
+    }
+}
+
+struct Row {
+    push: Duration,
+    pop: Duration,
+    iterate: Option<Duration>,
+    drop: Duration,
+    bytes: usize
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn filled<S: Subject>(elements: usize) -> S {
+    let mut list = S::default();
+    for i in 0..elements as u64 {
+        list.push(i);
+    }
+    list
+}
+
+fn measure<S: Subject>(elements: usize) -> Row {
+    let before = LIVE_BYTES.load(Ordering::Relaxed);
+    let mut list = S::default();
+    let push = time(|| {
+        for i in 0..elements as u64 {
+            list.push(black_box(i));
+        }
+    });
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed) - before;
+
+    let iterate = list.sum().is_some().then(|| {
+        time(|| {
+            for _ in 0..ITERATIONS {
+                black_box(list.sum());
+            }
+        })
+    });
+
+    let pop = time(|| {
+        while let Some(element) = list.pop() {
+            black_box(element);
+        }
+    });
+
+    let list = filled::<S>(elements);
+    let drop = time(|| std::mem::drop(list));
+
+    Row { push, pop, iterate, drop, bytes }
+}
+
+fn per_element(elapsed: Duration, elements: usize) -> String {
+    format!("{:.2}", elapsed.as_nanos() as f64 / elements as f64)
+}
+
+fn report<S: Subject>(elements: usize) {
+    let row = measure::<S>(elements);
+    println!(
+        "{},{},{},{},{},{},{:.2}",
+        S::NAME,
+        elements,
+        per_element(row.push, elements),
+        per_element(row.pop, elements),
+        row.iterate.map_or(String::new(), |iterate| per_element(iterate, elements * ITERATIONS)),
+        per_element(row.drop, elements),
+        row.bytes as f64 / elements as f64
+    );
+}
+
+fn main() {
+    // `cargo bench` passes flags like `--bench` through, so take the first plain number
+    let elements = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_ELEMENTS);
+
+    println!("list,elements,push_ns,pop_ns,iterate_ns,drop_ns,bytes_per_element");
+    report::<first::List<u64>>(elements);
+    report::<second::List<u64>>(elements);
+    report::<third::List<u64>>(elements);
+    report::<fourth::List<u64>>(elements);
+    report::<fifth::List<u64>>(elements);
+    report::<sixth::LinkedList<u64>>(elements);
+    report::<VecDeque<u64>>(elements);
+    report::<StdLinkedList<u64>>(elements);
+}
@@ -0,0 +1,63 @@
+//! Compares allocator traffic for `sixth::LinkedList` with and without a node cache.
+//!
+//! Run with `cargo bench --bench node_cache`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use linked_list::sixth::LinkedList;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const BATCH: usize = 1_000;
+const ROUNDS: usize = 2_000;
+
+// Fill up to `BATCH` elements and drain them again, like a queue in a hot loop
+fn churn(list: &mut LinkedList<u64>) {
+    for round in 0..ROUNDS {
+        for i in 0..BATCH {
+            list.push_back((round * BATCH + i) as u64);
+        }
+        for _ in 0..BATCH {
+            black_box(list.pop_front());
+        }
+    }
+}
+
+fn run(name: &str, mut list: LinkedList<u64>) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    churn(&mut list);
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    let operations = 2 * BATCH * ROUNDS;
+    println!(
+        "{:<12} {:>10} allocations {:>8.2} ns/op",
+        name,
+        allocations,
+        elapsed.as_nanos() as f64 / operations as f64
+    );
+}
+
+fn main() {
+    run("no cache", LinkedList::new());
+    run("node cache", LinkedList::with_node_cache(BATCH));
+}
@@ -0,0 +1,76 @@
+//! Compares `unrolled::UnrolledList` against `sixth::LinkedList` for pushing, iterating and
+//! draining the same elements.
+//!
+//! Run with `cargo bench --bench unrolled`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use linked_list::sixth::LinkedList;
+use linked_list::unrolled::UnrolledList;
+
+const ELEMENTS: usize = 1_000_000;
+const ITERATIONS: usize = 20;
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn report(name: &str, operation: &str, elapsed: Duration, operations: usize) {
+    println!(
+        "{:<10} {:<8} {:>8.2} ns/op",
+        name,
+        operation,
+        elapsed.as_nanos() as f64 / operations as f64
+    );
+}
+
+fn bench_sixth() {
+    let mut list = LinkedList::new();
+    let push = time(|| {
+        for i in 0..ELEMENTS as u64 {
+            list.push_back(i);
+        }
+    });
+    let iterate = time(|| {
+        for _ in 0..ITERATIONS {
+            black_box(list.iter().sum::<u64>());
+        }
+    });
+    let pop = time(|| {
+        while let Some(element) = list.pop_front() {
+            black_box(element);
+        }
+    });
+    report("sixth", "push", push, ELEMENTS);
+    report("sixth", "iterate", iterate, ELEMENTS * ITERATIONS);
+    report("sixth", "pop", pop, ELEMENTS);
+}
+
+fn bench_unrolled() {
+    let mut list: UnrolledList<u64> = UnrolledList::new();
+    let push = time(|| {
+        for i in 0..ELEMENTS as u64 {
+            list.push_back(i);
+        }
+    });
+    let iterate = time(|| {
+        for _ in 0..ITERATIONS {
+            black_box(list.iter().sum::<u64>());
+        }
+    });
+    let pop = time(|| {
+        while let Some(element) = list.pop_front() {
+            black_box(element);
+        }
+    });
+    report("unrolled", "push", push, ELEMENTS);
+    report("unrolled", "iterate", iterate, ELEMENTS * ITERATIONS);
+    report("unrolled", "pop", pop, ELEMENTS);
+}
+
+fn main() {
+    bench_sixth();
+    bench_unrolled();
+}
@@ -0,0 +1,225 @@
+//! A minimal stand-in for the unstable `std::alloc::Allocator` trait, so lists can put their
+//! nodes in arenas or other custom memory without a nightly compiler.
+
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use core::error::Error;
+use core::fmt;
+use core::ptr::{self, NonNull};
+#[cfg(any(test, feature = "std"))]
+use std::collections::HashMap;
+#[cfg(any(test, feature = "std"))]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// # Safety
+/// A block returned by `allocate` must stay valid until it is passed back to `deallocate` on
+/// the same allocator, and must fit `layout`.
+pub unsafe trait Allocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// # Safety
+    /// `ptr` must have come from `allocate` on this allocator with this same `layout`, and
+    /// must not have been deallocated already.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+}
+
+/// The global allocator, i.e. what `Box::new` uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            // Zero-sized blocks never touch the allocator, just like `Box`
+            return Ok(NonNull::new(ptr::without_provenance_mut(layout.align())).unwrap());
+        }
+        NonNull::new(unsafe { alloc(layout) }).ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl Error for AllocError {}
+
+// The `Box::new`/`Box::from_raw` pair the lists use, routed through an `Allocator`
+pub(crate) fn allocate_node<N, A: Allocator>(allocator: &A, node: N) -> NonNull<N> {
+    let layout = Layout::new::<N>();
+    let ptr = allocator.allocate(layout)
+        .unwrap_or_else(|_| handle_alloc_error(layout))
+        .cast::<N>();
+    unsafe { ptr.as_ptr().write(node); }
+    ptr
+}
+
+// Frees the node's memory without dropping it; move anything that needs dropping out first
+pub(crate) unsafe fn deallocate_node<N, A: Allocator>(allocator: &A, node: NonNull<N>) {
+    allocator.deallocate(node.cast(), Layout::new::<N>());
+}
+
+#[cfg(any(test, feature = "std"))]
+/// Wraps another allocator and keeps a ledger of every live block, for use in tests.
+///
+/// Freeing a pointer it never handed out, freeing one twice, or freeing with a different
+/// layout than it was allocated with panics instead of corrupting the heap.
+pub struct CountingAllocator<A: Allocator = Global> {
+    inner: A,
+    ledger: Mutex<Ledger>
+}
+
+#[cfg(any(test, feature = "std"))]
+#[derive(Default)]
+struct Ledger {
+    live: HashMap<usize, Layout>,
+    allocations: usize,
+    deallocations: usize
+}
+
+#[cfg(any(test, feature = "std"))]
+impl CountingAllocator {
+    pub fn new() -> Self {
+        CountingAllocator::wrapping(Global)
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl Default for CountingAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<A: Allocator> CountingAllocator<A> {
+    pub fn wrapping(inner: A) -> Self {
+        CountingAllocator { inner, ledger: Mutex::new(Ledger::default()) }
+    }
+
+    pub fn allocations(&self) -> usize {
+        self.ledger().allocations
+    }
+
+    pub fn deallocations(&self) -> usize {
+        self.ledger().deallocations
+    }
+
+    /// How many blocks have been allocated but not yet freed.
+    pub fn live(&self) -> usize {
+        self.ledger().live.len()
+    }
+
+    /// The total size of the blocks that are still live.
+    pub fn live_bytes(&self) -> usize {
+        self.ledger().live.values().map(Layout::size).sum()
+    }
+
+    fn ledger(&self) -> MutexGuard<'_, Ledger> {
+        self.ledger.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+unsafe impl<A: Allocator> Allocator for CountingAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.inner.allocate(layout)?;
+        let mut ledger = self.ledger();
+        ledger.allocations += 1;
+        // Zero-sized blocks share a dangling address, so only real blocks are tracked
+        if layout.size() != 0 {
+            ledger.live.insert(ptr.as_ptr() as usize, layout);
+        }
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        {
+            let mut ledger = self.ledger();
+            ledger.deallocations += 1;
+            if layout.size() != 0 {
+                match ledger.live.remove(&(ptr.as_ptr() as usize)) {
+                    Some(allocated) => assert_eq!(
+                        allocated, layout,
+                        "{:p} freed with a different layout than it was allocated with", ptr
+                    ),
+                    None => panic!("{:p} was not allocated by this allocator or was already freed", ptr)
+                }
+            }
+        }
+        self.inner.deallocate(ptr, layout);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::alloc::Layout;
+    use super::{Allocator, CountingAllocator};
+
+    #[test]
+    fn counts() {
+        let allocator = CountingAllocator::new();
+        let layout = Layout::new::<u64>();
+
+        let first = allocator.allocate(layout).unwrap();
+        let second = allocator.allocate(layout).unwrap();
+        assert_eq!(allocator.allocations(), 2);
+        assert_eq!(allocator.live(), 2);
+
+        unsafe {
+            allocator.deallocate(first, layout);
+            allocator.deallocate(second, layout);
+        }
+        assert_eq!(allocator.deallocations(), 2);
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "already freed")]
+    fn double_free() {
+        let allocator = CountingAllocator::new();
+        let layout = Layout::new::<u64>();
+        let ptr = allocator.allocate(layout).unwrap();
+        unsafe {
+            allocator.deallocate(ptr, layout);
+            allocator.deallocate(ptr, layout);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not allocated by this allocator")]
+    fn foreign_free() {
+        let ours = CountingAllocator::new();
+        let theirs = CountingAllocator::new();
+        let layout = Layout::new::<u64>();
+        let ptr = theirs.allocate(layout).unwrap();
+        unsafe { ours.deallocate(ptr, layout); }
+    }
+
+    #[test]
+    #[should_panic(expected = "different layout")]
+    fn mismatched_layout() {
+        let allocator = CountingAllocator::new();
+        let ptr = allocator.allocate(Layout::new::<u64>()).unwrap();
+        unsafe { allocator.deallocate(ptr, Layout::new::<u32>()); }
+    }
+}
@@ -0,0 +1,719 @@
+//! A deque with `sixth::LinkedList`'s API but no `unsafe`.
+//!
+//! Nodes live in a `Vec` slab and link to each other by index. Removed slots go on a free list
+//! and are reused by later pushes. Every slot carries a generation counter that is bumped when
+//! its node is removed, so a `NodeId` held from before can't alias whatever reuses the slot.
+
+#![forbid(unsafe_code)]
+
+use alloc::vec::Vec;
+use core::mem;
+
+pub struct ArenaList<T> {
+    slots: Vec<Slot<T>>,
+    head: Option<u32>,
+    tail: Option<u32>,
+    free: Option<u32>,
+    length: usize,
+    // The generation brand-new slots start at; raised by `compact` so stale ids never match
+    base_generation: u32,
+    // Leading slots that are never handed out again, set once `compact` runs out of generations
+    retired: u32,
+    // One past the highest index ever issued, including slots `compact` has since released
+    high_water: u32
+}
+
+// A slot that is never reused carries this generation, so no live id is ever issued with it
+const RETIRED: u32 = u32::MAX;
+
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>
+}
+
+enum Entry<T> {
+    Occupied(Node<T>),
+    Vacant { next_free: Option<u32> }
+}
+
+struct Node<T> {
+    previous: Option<u32>,
+    element: T,
+    next: Option<u32>
+}
+
+/// A handle to one element that stays valid until that element is removed or the list is
+/// compacted, no matter what happens to the rest of the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32
+}
+
+impl<T> ArenaList<T> {
+    pub fn new() -> Self {
+        ArenaList::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ArenaList {
+            slots: Vec::with_capacity(capacity),
+            head: None,
+            tail: None,
+            free: None,
+            length: 0,
+            base_generation: 0,
+            retired: 0,
+            high_water: 0
+        }
+    }
+
+    pub fn push_front(&mut self, element: T) -> NodeId {
+        self.insert_between(None, self.head, element)
+    }
+
+    pub fn push_back(&mut self, element: T) -> NodeId {
+        self.insert_between(self.tail, None, element)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| self.unlink(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|tail| self.unlink(tail))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|head| &self.node(head).element)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|head| &mut self.node_mut(head).element)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|tail| &self.node(tail).element)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|tail| &mut self.node_mut(tail).element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.resolve(id).is_some()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.resolve(id).map(|index| &self.node(index).element)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.resolve(id).map(|index| &mut self.node_mut(index).element)
+    }
+
+    /// Unlinks the element `id` refers to in O(1). Returns `None` if it was already removed.
+    pub fn remove(&mut self, id: NodeId) -> Option<T> {
+        self.resolve(id).map(|index| self.unlink(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { list: self, head: self.head, tail: self.tail, length: self.length }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor { list: self, current: self.head }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor { list: self, current: self.tail }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.head, list: self }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.tail, list: self }
+    }
+
+    /// A cursor on the element `id` refers to, or `None` if it has been removed.
+    pub fn cursor_at_mut(&mut self, id: NodeId) -> Option<CursorMut<'_, T>> {
+        self.resolve(id).map(move |index| CursorMut { current: Some(index), list: self })
+    }
+
+    /// Number of slots in the slab, free ones included.
+    pub fn slab_len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Moves every element into the front of the slab in list order and releases the free
+    /// slots. This invalidates every `NodeId` handed out so far.
+    ///
+    /// Once the slot generations are exhausted, every index issued so far is retired for good
+    /// and the elements move past them, so a stale id can never match again.
+    pub fn compact(&mut self) {
+        self.compact_with(|_, _| {});
+    }
+
+    /// Like `compact`, but reports each element's old and new id so callers can update any
+    /// ids they are holding on to.
+    pub fn compact_with<F: FnMut(NodeId, NodeId)>(&mut self, mut remap: F) {
+        // One generation newer than anything issued so far, so no old id can match afterwards
+        let newest = self.slots[self.retired as usize..].iter()
+            .map(|slot| slot.generation)
+            .fold(self.base_generation, u32::max);
+        let generation = match newest.checked_add(1).filter(|&generation| generation != RETIRED) {
+            Some(generation) => generation,
+            None => {
+                self.retired = self.high_water;
+                0
+            }
+        };
+
+        let mut old_slots = mem::take(&mut self.slots);
+        let first = self.retired;
+        let mut slots = Vec::with_capacity(first as usize + self.length);
+        slots.resize_with(first as usize, || Slot { generation: RETIRED, entry: Entry::Vacant { next_free: None } });
+        let mut current = self.head;
+        while let Some(old_index) = current {
+            let slot = mem::replace(&mut old_slots[old_index as usize], Slot {
+                generation: 0,
+                entry: Entry::Vacant { next_free: None }
+            });
+            let Entry::Occupied(node) = slot.entry else {
+                unreachable!("linked slot {} is vacant", old_index);
+            };
+            current = node.next;
+
+            let index = u32::try_from(slots.len()).expect("ArenaList can't hold more than u32::MAX slots");
+            let previous = index.checked_sub(1).filter(|&previous| previous >= first);
+            remap(NodeId { index: old_index, generation: slot.generation }, NodeId { index, generation });
+            slots.push(Slot {
+                generation,
+                entry: Entry::Occupied(Node { previous, element: node.element, next: None })
+            });
+            if let Some(previous) = previous {
+                Self::occupied_mut(&mut slots, previous).next = Some(index);
+            }
+        }
+
+        self.high_water = self.high_water.max(slots.len() as u32);
+        self.slots = slots;
+        self.head = if self.length > 0 { Some(first) } else { None };
+        self.tail = self.length.checked_sub(1).map(|last| first + last as u32);
+        self.free = None;
+        self.base_generation = generation;
+    }
+
+    fn resolve(&self, id: NodeId) -> Option<u32> {
+        match self.slots.get(id.index as usize) {
+            Some(Slot { generation, entry: Entry::Occupied(_) }) if *generation == id.generation => Some(id.index),
+            _ => None
+        }
+    }
+
+    fn id(&self, index: u32) -> NodeId {
+        NodeId { index, generation: self.slots[index as usize].generation }
+    }
+
+    fn node(&self, index: u32) -> &Node<T> {
+        match &self.slots[index as usize].entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant { .. } => unreachable!("linked slot {} is vacant", index)
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut Node<T> {
+        Self::occupied_mut(&mut self.slots, index)
+    }
+
+    fn occupied_mut(slots: &mut [Slot<T>], index: u32) -> &mut Node<T> {
+        match &mut slots[index as usize].entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant { .. } => unreachable!("linked slot {} is vacant", index)
+        }
+    }
+
+    fn insert_between(&mut self, previous: Option<u32>, next: Option<u32>, element: T) -> NodeId {
+        let entry = Entry::Occupied(Node { previous, element, next });
+        let index = match self.free {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                let Entry::Vacant { next_free } = mem::replace(&mut slot.entry, entry) else {
+                    unreachable!("free slot {} is occupied", index);
+                };
+                self.free = next_free;
+                index
+            }
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("ArenaList can't hold more than u32::MAX slots");
+                self.slots.push(Slot { generation: self.base_generation, entry });
+                self.high_water = self.high_water.max(index + 1);
+                index
+            }
+        };
+
+        match previous {
+            Some(previous) => self.node_mut(previous).next = Some(index),
+            None => self.head = Some(index)
+        }
+        match next {
+            Some(next) => self.node_mut(next).previous = Some(index),
+            None => self.tail = Some(index)
+        }
+
+        self.length += 1;
+        self.id(index)
+    }
+
+    fn unlink(&mut self, index: u32) -> T {
+        let slot = &mut self.slots[index as usize];
+        let Entry::Occupied(node) = mem::replace(&mut slot.entry, Entry::Vacant { next_free: None }) else {
+            unreachable!("linked slot {} is vacant", index);
+        };
+
+        // A slot whose generation would wrap is retired instead of risking an old id matching it
+        slot.generation = slot.generation.wrapping_add(1);
+        if slot.generation != RETIRED {
+            slot.entry = Entry::Vacant { next_free: self.free };
+            self.free = Some(index);
+        }
+
+        match node.previous {
+            Some(previous) => self.node_mut(previous).next = node.next,
+            None => self.head = node.next
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).previous = node.previous,
+            None => self.tail = node.previous
+        }
+
+        self.length -= 1;
+        node.element
+    }
+}
+
+impl<T> Default for ArenaList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArenaList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a ArenaList<T>,
+    head: Option<u32>,
+    tail: Option<u32>,
+    length: usize
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.head.map(|head| {
+                let node = self.list.node(head);
+                self.head = node.next;
+                self.length -= 1;
+                &node.element
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.tail.map(|tail| {
+                let node = self.list.node(tail);
+                self.tail = node.previous;
+                self.length -= 1;
+                &node.element
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
+    fn len(&self) -> usize {
+        self.length
+    }
+}
+
+pub struct IntoIter<T> {
+    list: ArenaList<T>
+}
+
+impl<T> IntoIterator for ArenaList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+/// A read-only position in the list. Moving past either end lands on a "ghost" position
+/// (where `current` is `None`), and moving once more wraps around to the other end.
+pub struct Cursor<'a, T> {
+    list: &'a ArenaList<T>,
+    current: Option<u32>
+}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn current(&self) -> Option<&'a T> {
+        self.current.map(|index| &self.list.node(index).element)
+    }
+
+    pub fn id(&self) -> Option<NodeId> {
+        self.current.map(|index| self.list.id(index))
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            Some(index) => self.list.node(index).next,
+            None => self.list.head
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            Some(index) => self.list.node(index).previous,
+            None => self.list.tail
+        };
+    }
+}
+
+/// A position in the list that can also edit around itself. It moves like `Cursor`.
+pub struct CursorMut<'a, T> {
+    list: &'a mut ArenaList<T>,
+    current: Option<u32>
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current.map(|index| &mut self.list.node_mut(index).element)
+    }
+
+    pub fn id(&self) -> Option<NodeId> {
+        self.current.map(|index| self.list.id(index))
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            Some(index) => self.list.node(index).next,
+            None => self.list.head
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            Some(index) => self.list.node(index).previous,
+            None => self.list.tail
+        };
+    }
+
+    /// Inserts after the cursor, or at the front if the cursor is on the ghost position.
+    pub fn insert_after(&mut self, element: T) -> NodeId {
+        match self.current {
+            Some(index) => {
+                let next = self.list.node(index).next;
+                self.list.insert_between(Some(index), next, element)
+            }
+            None => self.list.push_front(element)
+        }
+    }
+
+    /// Inserts before the cursor, or at the back if the cursor is on the ghost position.
+    pub fn insert_before(&mut self, element: T) -> NodeId {
+        match self.current {
+            Some(index) => {
+                let previous = self.list.node(index).previous;
+                self.list.insert_between(previous, Some(index), element)
+            }
+            None => self.list.push_back(element)
+        }
+    }
+
+    /// Removes the current element and moves the cursor on to the one after it.
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.current.map(|index| {
+            self.current = self.list.node(index).next;
+            self.list.unlink(index)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ArenaList;
+
+    #[test]
+    fn basics() {
+        let mut list = ArenaList::new();
+        list.push_front(3);
+        list.push_front(6);
+        list.push_front(2);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(6));
+        list.push_front(4);
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn peek() {
+        let mut list = ArenaList::new();
+        assert_eq!(list.front(), None);
+        assert_eq!(list.back_mut(), None);
+
+        list.push_front(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+
+        *list.front_mut().unwrap() = -1;
+        *list.back_mut().unwrap() = -3;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![-1, 2, -3]);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn slots_are_reused() {
+        let mut list = ArenaList::new();
+        for i in 0..4 {
+            list.push_back(i);
+        }
+        list.pop_front();
+        list.pop_back();
+        assert_eq!(list.slab_len(), 4);
+
+        list.push_back(10);
+        list.push_front(11);
+        assert_eq!(list.slab_len(), 4);
+        list.push_back(12);
+        assert_eq!(list.slab_len(), 5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![11, 1, 2, 10, 12]);
+    }
+
+    #[test]
+    fn node_ids() {
+        let mut list = ArenaList::new();
+        let a = list.push_back("a");
+        let b = list.push_back("b");
+        let c = list.push_back("c");
+
+        *list.get_mut(b).unwrap() = "B";
+        assert_eq!(list.get(b), Some(&"B"));
+
+        // Removing from the middle relinks the neighbours
+        assert_eq!(list.remove(b), Some("B"));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec!["a", "c"]);
+
+        // The stale id doesn't resolve, even once its slot has been reused
+        let d = list.push_front("d");
+        assert!(!list.contains(b));
+        assert_eq!(list.get(b), None);
+        assert_eq!(list.remove(b), None);
+        assert_eq!(list.get(d), Some(&"d"));
+
+        assert_eq!(list.get(a), Some(&"a"));
+        assert_eq!(list.remove(c), Some("c"));
+        assert_eq!(list.back(), Some(&"a"));
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = ArenaList::new();
+        for i in 1..=7 {
+            list.push_back(i);
+        }
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&7));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next(), Some(1));
+        assert_eq!(into_iter.next_back(), Some(7));
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list = ArenaList::new();
+        for i in 1..=3 {
+            list.push_back(i);
+        }
+
+        let mut cursor = list.cursor_front();
+        assert_eq!(cursor.current(), Some(&1));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&3));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&1));
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&3));
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut list = ArenaList::new();
+        let two = list.push_back(2);
+        list.push_back(4);
+
+        let mut cursor = list.cursor_at_mut(two).unwrap();
+        cursor.insert_before(1);
+        let three = cursor.insert_after(3);
+        *cursor.current().unwrap() *= 10;
+        cursor.move_next();
+        assert_eq!(cursor.id(), Some(three));
+
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.current(), Some(&mut 4));
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), None);
+
+        // On the ghost position, inserts go to the ends
+        cursor.insert_after(0);
+        cursor.insert_before(5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 20, 5]);
+        assert!(!list.contains(three));
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(20));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.back(), Some(&5));
+    }
+
+    #[test]
+    fn compact() {
+        let mut list = ArenaList::new();
+        let ids: Vec<_> = (0..10).map(|i| list.push_back(i)).collect();
+        for &id in ids.iter().step_by(2) {
+            list.remove(id);
+        }
+        assert_eq!(list.slab_len(), 10);
+
+        let mut remapped = Vec::new();
+        list.compact_with(|old, new| remapped.push((old, new)));
+        assert_eq!(list.slab_len(), 5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+
+        // Old ids no longer resolve, and the new ones point at the same elements
+        for (i, (old, new)) in remapped.into_iter().enumerate() {
+            assert_eq!(old, ids[2 * i + 1]);
+            assert!(!list.contains(old));
+            assert_eq!(list.get(new), Some(&(2 * i + 1)));
+        }
+        for &id in &ids {
+            assert_eq!(list.get(id), None);
+        }
+
+        // The compacted list is still fully linked in both directions
+        let fresh = list.push_front(100);
+        list.push_back(200);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![200, 9, 7, 5, 3, 1, 100]);
+        assert_eq!(list.remove(fresh), Some(100));
+
+        let mut empty: ArenaList<i32> = ArenaList::new();
+        empty.push_back(1);
+        empty.pop_back();
+        empty.compact();
+        assert_eq!(empty.slab_len(), 0);
+        assert_eq!(empty.front(), None);
+        empty.push_back(2);
+        assert_eq!(empty.pop_front(), Some(2));
+    }
+
+    #[test]
+    fn compact_retires_exhausted_generations() {
+        let mut list = ArenaList::new();
+        list.base_generation = u32::MAX - 3;
+        let first = list.push_back(1);
+        let second = list.push_back(2);
+        list.pop_back();
+        assert_eq!(list.slab_len(), 2);
+
+        // The last usable generation still compacts in place
+        list.compact();
+        let compacted = list.cursor_front().id().unwrap();
+        assert_eq!((compacted.index, compacted.generation), (0, u32::MAX - 1));
+
+        // After that both indices ever issued are retired and the element moves past them
+        list.push_back(3);
+        list.compact();
+        assert_eq!(list.slab_len(), 4);
+        for id in [first, second, compacted] {
+            assert!(!list.contains(id));
+        }
+        let ids: Vec<_> = (4..100).map(|i| list.push_front(i)).collect();
+        assert!(ids.iter().all(|id| id.index >= 2));
+        while list.pop_front().is_some() {}
+        assert!((0..50).map(|i| list.push_back(i)).all(|id| id.index >= 2));
+        assert_eq!(list.back(), Some(&49));
+
+        // Later compactions go back to bumping the generation, behind the retired slots
+        list.compact();
+        assert_eq!(list.slab_len(), 52);
+        let front = list.cursor_front().id().unwrap();
+        assert_eq!(front.index, 2);
+        assert!(front.generation > 0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (0..50).collect::<Vec<_>>());
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), (0..50).rev().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,494 @@
+//! A bounded async queue over `sixth::LinkedList` that doesn't depend on any runtime.
+//!
+//! Both halves park their task's `Waker` in the shared state when they can't make progress:
+//! the receiver is woken by the next push or by the last sender going away, and waiting
+//! senders are woken whenever an element is taken or the receiver is dropped.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use crate::channel::{SendError, TryRecvError};
+use crate::sixth::LinkedList;
+
+/// The `poll_next` half of an async iterator, matching the shape of `futures::Stream`.
+pub trait Stream {
+    type Item;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    capacity: usize
+}
+
+struct State<T> {
+    queue: LinkedList<T>,
+    senders: usize,
+    receiver_alive: bool,
+    receiver_waker: Option<Waker>,
+    // One slot per waiting `SendFuture`, so polling again replaces its waker instead of piling up
+    sender_wakers: HashMap<usize, Waker>,
+    next_waiter: usize
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> State<T> {
+    fn wake_receiver(&mut self) {
+        if let Some(waker) = self.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+
+    // A woken task may drop its future instead of polling it, so waking just one could lose
+    // the wake-up; every waiting sender gets to retry instead.
+    fn wake_senders(&mut self) {
+        for (_, waker) in self.sender_wakers.drain() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>
+}
+
+/// Creates a queue that holds at most `capacity` elements; `send` waits while it is full.
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "a bounded queue needs room for at least one element");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: LinkedList::new(),
+            senders: 1,
+            receiver_alive: true,
+            receiver_waker: None,
+            sender_wakers: HashMap::new(),
+            next_waiter: 0
+        }),
+        capacity
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+impl<T> Sender<T> {
+    pub fn try_send(&self, element: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.shared.lock();
+        if !state.receiver_alive {
+            return Err(TrySendError::Disconnected(element));
+        }
+        if state.queue.len() >= self.shared.capacity {
+            return Err(TrySendError::Full(element));
+        }
+
+        state.queue.push_back(element);
+        state.wake_receiver();
+        Ok(())
+    }
+
+    /// Resolves once `element` has been queued, or hands it back if the receiver is gone.
+    pub fn send(&self, element: T) -> SendFuture<'_, T> {
+        SendFuture { sender: self, element: Some(element), waiter: None }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            state.wake_receiver();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+        match state.queue.pop_front() {
+            Some(element) => {
+                state.wake_senders();
+                Ok(element)
+            }
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Resolves to the next element, or to `None` once the queue is empty and every sender
+    /// has been dropped.
+    pub fn recv(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.shared.lock();
+        if let Some(element) = state.queue.pop_front() {
+            state.wake_senders();
+            Poll::Ready(Some(element))
+        } else if state.senders == 0 {
+            Poll::Ready(None)
+        } else {
+            state.receiver_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.wake_senders();
+        let queue = std::mem::take(&mut state.queue);
+        drop(state);
+        drop(queue);
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    element: Option<T>,
+    // This future's key in `sender_wakers`, assigned the first time it has to wait
+    waiter: Option<usize>
+}
+
+// The element is only ever moved out by value, never pinned in place
+impl<'a, T> Unpin for SendFuture<'a, T> {}
+
+impl<'a, T> Future for SendFuture<'a, T> {
+    type Output = Result<(), SendError<T>>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.sender.shared.lock();
+
+        let element = this.element.take().expect("SendFuture polled after completion");
+        if !state.receiver_alive {
+            return Poll::Ready(Err(SendError(element)));
+        }
+        if state.queue.len() >= this.sender.shared.capacity {
+            this.element = Some(element);
+            let state = &mut *state;
+            let waiter = *this.waiter.get_or_insert_with(|| {
+                state.next_waiter = state.next_waiter.wrapping_add(1);
+                state.next_waiter
+            });
+            // `clone_from` skips the clone when the stored waker already wakes the same task
+            state.sender_wakers
+                .entry(waiter)
+                .and_modify(|waker| waker.clone_from(cx.waker()))
+                .or_insert_with(|| cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        if let Some(waiter) = this.waiter.take() {
+            state.sender_wakers.remove(&waiter);
+        }
+        state.queue.push_back(element);
+        state.wake_receiver();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, T> Drop for SendFuture<'a, T> {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter {
+            self.sender.shared.lock().sender_wakers.remove(&waiter);
+        }
+    }
+}
+
+pub struct RecvFuture<'a, T> {
+    receiver: &'a mut Receiver<T>
+}
+
+impl<'a, T> Future for RecvFuture<'a, T> {
+    type Output = Option<T>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+#[derive(PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T)
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(element) | TrySendError::Disconnected(element) => element
+        }
+    }
+}
+
+// Prints only the variant; the element is left out since callers get it back with `into_inner`
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_) => f.write_str("Disconnected(..)")
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("sending on a full queue"),
+            TrySendError::Disconnected(_) => f.write_str("sending on a queue whose receiver has been dropped")
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+    use crate::channel::{SendError, TryRecvError};
+    use super::{bounded, Stream, TrySendError};
+
+    // The whole executor: park the thread until the future's waker unparks it
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    // Runs several futures on the current thread, only re-polling the ones that were woken.
+    // If nothing has been woken but some futures are unfinished, a wake-up was lost.
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn run_all(mut tasks: Vec<Pin<Box<dyn Future<Output = ()> + '_>>>) {
+        let flags: Vec<_> = tasks.iter().map(|_| Arc::new(Flag(AtomicBool::new(true)))).collect();
+        let mut done = vec![false; tasks.len()];
+        while done.contains(&false) {
+            let mut progressed = false;
+            for (i, task) in tasks.iter_mut().enumerate() {
+                if done[i] || !flags[i].0.swap(false, Ordering::SeqCst) {
+                    continue;
+                }
+                progressed = true;
+                let waker = Waker::from(flags[i].clone());
+                if task.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+                    done[i] = true;
+                }
+            }
+            assert!(progressed, "every unfinished task is waiting on a wake-up that never came");
+        }
+    }
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn basics() {
+        let (sender, mut receiver) = bounded(4);
+        block_on(async {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+            assert_eq!(receiver.recv().await, Some(1));
+            assert_eq!(receiver.recv().await, Some(2));
+        });
+
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        sender.try_send(3).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(3));
+
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(block_on(receiver.recv()), None);
+    }
+
+    #[test]
+    fn recv_is_woken_by_push() {
+        let (sender, mut receiver) = bounded(1);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Pin::new(&mut receiver).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        sender.try_send(5).unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut receiver).poll_next(&mut cx), Poll::Ready(Some(5)));
+
+        // Dropping the last sender also wakes the receiver so it can see the end of the stream
+        assert_eq!(Pin::new(&mut receiver).poll_next(&mut cx), Poll::Pending);
+        drop(sender);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(Pin::new(&mut receiver).poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn send_waits_while_full() {
+        let (sender, mut receiver) = bounded(1);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(sender.try_send(1), Ok(()));
+        assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+
+        let mut send = sender.send(2);
+        assert!(Pin::new(&mut send).poll(&mut cx).is_pending());
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(receiver.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn repolled_send_keeps_one_waker() {
+        let (sender, _receiver) = bounded(1);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+        sender.try_send(1).unwrap();
+
+        let mut send = sender.send(2);
+        for _ in 0..100 {
+            assert!(Pin::new(&mut send).poll(&mut cx).is_pending());
+        }
+        assert_eq!(sender.shared.lock().sender_wakers.len(), 1);
+
+        // A second waiting future gets its own slot, and dropping either gives its slot back
+        let mut other = sender.send(3);
+        assert!(Pin::new(&mut other).poll(&mut cx).is_pending());
+        assert_eq!(sender.shared.lock().sender_wakers.len(), 2);
+        drop(send);
+        drop(other);
+        assert!(sender.shared.lock().sender_wakers.is_empty());
+    }
+
+    #[test]
+    fn send_fails_once_receiver_dropped() {
+        let (sender, receiver) = bounded(1);
+        sender.try_send(1).unwrap();
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut send = sender.send(2);
+        assert!(Pin::new(&mut send).poll(&mut Context::from_waker(&waker)).is_pending());
+
+        drop(receiver);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(block_on(send), Err(SendError(2)));
+        assert_eq!(sender.try_send(3).map_err(TrySendError::into_inner), Err(3));
+    }
+
+    #[test]
+    fn producer_and_consumer_tasks() {
+        let (sender, mut receiver) = bounded(2);
+        let mut received = Vec::new();
+
+        let producer = {
+            let sender = sender.clone();
+            async move {
+                for i in 0..100 {
+                    sender.send(i).await.unwrap();
+                }
+            }
+        };
+        drop(sender);
+        let consumer = async {
+            while let Some(element) = receiver.recv().await {
+                received.push(element);
+            }
+        };
+
+        run_all(vec![Box::pin(consumer), Box::pin(producer)]);
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn across_threads() {
+        let (sender, mut receiver) = bounded(8);
+        let handles: Vec<_> = (0..4).map(|producer| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    for i in 0..500 {
+                        sender.send((producer, i)).await.unwrap();
+                    }
+                });
+            })
+        }).collect();
+        drop(sender);
+
+        let mut next = [0; 4];
+        block_on(async {
+            while let Some((producer, i)) = receiver.recv().await {
+                assert_eq!(next[producer], i);
+                next[producer] += 1;
+            }
+        });
+        assert_eq!(next, [500; 4]);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
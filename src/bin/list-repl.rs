@@ -0,0 +1,273 @@
+//! An interactive shell for poking at the lists. Keeps named lists of any implementation and
+//! prints each one's contents after every command that touches it; type `help` for commands.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use linked_list::debug_viz::{Graph, ToDot};
+use linked_list::replay::{self, Implementation, Op, Target};
+use linked_list::{fifth, first, fourth, second, sixth, third};
+
+const HELP: &str = "commands:
+  new IMPL NAME          create an empty list (first, second, third, fourth, fifth, sixth, vecdeque)
+  push_front NAME N      push_back NAME N
+  pop_front NAME         pop_back NAME
+  front NAME             back NAME             len NAME
+  show NAME              print a list's elements front to back
+  dot NAME...            print the lists' node graph as Graphviz DOT
+  share NEW NAME         make NEW a third list sharing NAME's nodes, to prepend onto separately
+  drop NAME              forget a list
+  lists                  name every list
+  help                   show this message
+  quit                   leave (so does end of input)";
+
+enum List {
+    First(first::List<i64>),
+    Second(second::List<i64>),
+    Third(third::List<i64>),
+    Fourth(fourth::List<i64>),
+    Fifth(fifth::List<i64>),
+    Sixth(sixth::LinkedList<i64>),
+    VecDeque(std::collections::VecDeque<i64>)
+}
+
+impl List {
+    fn new(implementation: Implementation) -> List {
+        match implementation {
+            Implementation::First => List::First(first::List::new()),
+            Implementation::Second => List::Second(second::List::new()),
+            Implementation::Third => List::Third(third::List::new()),
+            Implementation::Fourth => List::Fourth(fourth::List::new()),
+            Implementation::Fifth => List::Fifth(fifth::List::new()),
+            Implementation::Sixth => List::Sixth(sixth::LinkedList::new()),
+            Implementation::VecDeque => List::VecDeque(Default::default())
+        }
+    }
+
+    fn implementation(&self) -> Implementation {
+        match self {
+            List::First(_) => Implementation::First,
+            List::Second(_) => Implementation::Second,
+            List::Third(_) => Implementation::Third,
+            List::Fourth(_) => Implementation::Fourth,
+            List::Fifth(_) => Implementation::Fifth,
+            List::Sixth(_) => Implementation::Sixth,
+            List::VecDeque(_) => Implementation::VecDeque
+        }
+    }
+
+    fn target(&mut self) -> &mut dyn Target {
+        match self {
+            List::First(list) => list,
+            List::Second(list) => list,
+            List::Third(list) => list,
+            List::Fourth(list) => list,
+            List::Fifth(list) => list,
+            List::Sixth(list) => list,
+            List::VecDeque(list) => list
+        }
+    }
+
+    fn graph(&self) -> Option<&dyn ToDot> {
+        match self {
+            List::First(list) => Some(list),
+            List::Second(list) => Some(list),
+            List::Third(list) => Some(list),
+            List::Fourth(list) => Some(list),
+            List::Fifth(list) => Some(list),
+            List::Sixth(list) => Some(list),
+            List::VecDeque(_) => None
+        }
+    }
+
+    fn elements(&mut self) -> Vec<i64> {
+        match self {
+            // Neither has a borrowing iterator, so drain the list and build it back up
+            List::First(list) => {
+                let elements: Vec<_> = std::iter::from_fn(|| list.pop()).collect();
+                for &element in elements.iter().rev() {
+                    list.push(element);
+                }
+                elements
+            }
+            List::Fourth(list) => {
+                let elements: Vec<_> = std::iter::from_fn(|| list.pop_head()).collect();
+                for &element in &elements {
+                    list.push_tail(element);
+                }
+                elements
+            }
+            List::Second(list) => list.iter().copied().collect(),
+            List::Third(list) => list.iter().copied().collect(),
+            List::Fifth(list) => list.iter().copied().collect(),
+            List::Sixth(list) => list.iter().copied().collect(),
+            List::VecDeque(list) => list.iter().copied().collect()
+        }
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    lists: BTreeMap<String, List>
+}
+
+impl Session {
+    /// Runs one command line, returning what to print or an error message.
+    fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_string()),
+            ["lists"] => Ok(self
+                .lists
+                .iter()
+                .map(|(name, list)| format!("{} ({})", name, list.implementation()))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ["new", implementation, name] => {
+                let implementation = implementation.parse()?;
+                self.lists.insert(name.to_string(), List::new(implementation));
+                self.show(name)
+            }
+            ["share", new, name] => {
+                let shared = match self.list(name)? {
+                    List::Third(list) => list.clone(),
+                    list => return Err(format!("`{}` is a {} list; only third lists share nodes", name, list.implementation()))
+                };
+                self.lists.insert(new.to_string(), List::Third(shared));
+                self.show(new)
+            }
+            ["show", name] => self.show(name),
+            ["dot", names @ ..] if !names.is_empty() => {
+                let mut graph = Graph::new();
+                for name in names {
+                    let list = self.list(name)?;
+                    let list = list
+                        .graph()
+                        .ok_or_else(|| format!("`{}` is a {} list, which can't be drawn", name, list.implementation()))?;
+                    graph.add(name, list);
+                }
+                Ok(graph.finish().trim_end().to_string())
+            }
+            ["drop", name] => {
+                self.lists.remove(*name).ok_or_else(|| format!("no list named `{}`", name))?;
+                Ok(format!("dropped {}", name))
+            }
+            [operation, name, rest @ ..] => {
+                let op: Op = format!("{} {}", operation, rest.join(" ")).parse()?;
+                let list = self.list(name)?;
+                let outcome = replay::apply(list.target(), op);
+                if outcome == replay::Outcome::Unsupported {
+                    return Err(format!("{} lists don't support `{}`", list.implementation(), operation));
+                }
+                Ok(format!("{}\n{}", outcome, self.show(name)?))
+            }
+            [command, ..] => Err(format!("unknown command `{}`; try `help`", command))
+        }
+    }
+
+    fn list(&mut self, name: &str) -> Result<&mut List, String> {
+        self.lists.get_mut(name).ok_or_else(|| format!("no list named `{}`", name))
+    }
+
+    fn show(&mut self, name: &str) -> Result<String, String> {
+        let list = self.list(name)?;
+        Ok(format!("{} ({}) = {:?}", name, list.implementation(), list.elements()))
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut session = Session::default();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut line = String::new();
+    loop {
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            writeln!(stdout)?;
+            return Ok(());
+        }
+        if matches!(line.trim(), "quit" | "exit") {
+            return Ok(());
+        }
+        match session.execute(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => writeln!(stdout, "{}", output)?,
+            Err(message) => writeln!(stdout, "error: {}", message)?
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Session;
+
+    fn run(session: &mut Session, line: &str) -> String {
+        session.execute(line).unwrap_or_else(|error| panic!("`{}` failed: {}", line, error))
+    }
+
+    #[test]
+    fn deque_commands() {
+        let mut session = Session::default();
+        assert_eq!(run(&mut session, "new sixth a"), "a (sixth) = []");
+        assert_eq!(run(&mut session, "push_back a 3"), "ok\na (sixth) = [3]");
+        run(&mut session, "push_front a 1");
+        assert_eq!(run(&mut session, "back a"), "3\na (sixth) = [1, 3]");
+        assert_eq!(run(&mut session, "pop_front a"), "1\na (sixth) = [3]");
+        assert_eq!(run(&mut session, "len a"), "1\na (sixth) = [3]");
+        assert!(run(&mut session, "dot a").starts_with("digraph lists {"));
+
+        run(&mut session, "new fourth b");
+        assert_eq!(run(&mut session, "lists"), "a (sixth)\nb (fourth)");
+        assert_eq!(run(&mut session, "drop a"), "dropped a");
+        assert_eq!(run(&mut session, "lists"), "b (fourth)");
+    }
+
+    #[test]
+    fn show_keeps_drained_lists() {
+        let mut session = Session::default();
+        run(&mut session, "new first s");
+        run(&mut session, "push_front s 1");
+        run(&mut session, "push_front s 2");
+        assert_eq!(run(&mut session, "show s"), "s (first) = [2, 1]");
+        assert_eq!(run(&mut session, "pop_front s"), "2\ns (first) = [1]");
+
+        run(&mut session, "new fourth q");
+        run(&mut session, "push_back q 1");
+        run(&mut session, "push_back q 2");
+        assert_eq!(run(&mut session, "show q"), "q (fourth) = [1, 2]");
+        assert_eq!(run(&mut session, "show q"), "q (fourth) = [1, 2]");
+    }
+
+    #[test]
+    fn shared_third_lists() {
+        let mut session = Session::default();
+        run(&mut session, "new third a");
+        run(&mut session, "push_front a 1");
+        assert_eq!(run(&mut session, "share b a"), "b (third) = [1]");
+        run(&mut session, "push_front b 2");
+        run(&mut session, "push_front a 3");
+        assert_eq!(run(&mut session, "show a"), "a (third) = [3, 1]");
+        assert_eq!(run(&mut session, "show b"), "b (third) = [2, 1]");
+        // The shared node is drawn once, held by both branches
+        assert!(run(&mut session, "dot a b").contains("|1|rc=2}"));
+    }
+
+    #[test]
+    fn errors_leave_the_session_usable() {
+        let mut session = Session::default();
+        let error = |session: &mut Session, line| session.execute(line).unwrap_err();
+        assert_eq!(error(&mut session, "show a"), "no list named `a`");
+        assert_eq!(error(&mut session, "new seventh a"), "unknown implementation `seventh`");
+        run(&mut session, "new second a");
+        assert_eq!(error(&mut session, "pop_back a"), "second lists don't support `pop_back`");
+        assert_eq!(error(&mut session, "push_back a"), "`push_back` needs an element");
+        assert_eq!(error(&mut session, "push_front a x"), "`x` isn't an integer element");
+        assert_eq!(error(&mut session, "share b a"), "`a` is a second list; only third lists share nodes");
+        assert_eq!(error(&mut session, "frobnicate"), "unknown command `frobnicate`; try `help`");
+        run(&mut session, "new vecdeque v");
+        assert_eq!(error(&mut session, "dot v"), "`v` is a vecdeque list, which can't be drawn");
+        assert_eq!(run(&mut session, "push_front a 4"), "ok\na (second) = [4]");
+    }
+}
@@ -0,0 +1,106 @@
+//! Replays a trace of deque operations against one of the lists, or diffs two of them.
+//!
+//! Usage: `list-replay [--impl NAME] [--diff NAME] [TRACE]`, reading the trace from stdin
+//! when no file is given. See `linked_list::replay` for the trace format.
+
+use std::io::{self, Read};
+use std::process::ExitCode;
+use linked_list::replay::{self, Implementation};
+
+const USAGE: &str = "usage: list-replay [--impl NAME] [--diff NAME] [TRACE]
+  --impl NAME   list to replay against (default: sixth)
+  --diff NAME   also replay against NAME and report where the two disagree
+  TRACE         trace file to read (default: stdin)
+  NAME is one of: first, second, third, fourth, fifth, sixth, vecdeque";
+
+struct Options {
+    implementation: Implementation,
+    diff: Option<Implementation>,
+    path: Option<String>
+}
+
+// `None` when help was asked for
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options { implementation: Implementation::Sixth, diff: None, path: None };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--impl" | "--diff" => {
+                let name = args.next().ok_or_else(|| format!("`{}` needs an implementation name", arg))?;
+                let implementation = name.parse()?;
+                if arg == "--impl" {
+                    options.implementation = implementation;
+                } else {
+                    options.diff = Some(implementation);
+                }
+            }
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if options.path.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => options.path = Some(arg)
+        }
+    }
+    Ok(Some(options))
+}
+
+fn read_trace(path: Option<&str>) -> io::Result<String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut trace = String::new();
+            io::stdin().read_to_string(&mut trace)?;
+            Ok(trace)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("list-replay: {}", message);
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let ops = match read_trace(options.path.as_deref()) {
+        Ok(trace) => replay::parse(&trace),
+        Err(error) => {
+            eprintln!("list-replay: can't read trace: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    let ops = match ops {
+        Ok(ops) => ops,
+        Err(error) => {
+            eprintln!("list-replay: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(other) = options.diff else {
+        for (&(line, op), outcome) in ops.iter().zip(replay::run(options.implementation, &ops)) {
+            println!("{}: {} -> {}", line, op, outcome);
+        }
+        return ExitCode::SUCCESS;
+    };
+
+    let divergences = replay::diff(options.implementation, other, &ops);
+    for divergence in &divergences {
+        println!(
+            "{}: {} -> {} {}, {} {}",
+            divergence.line, divergence.op, options.implementation, divergence.left, other, divergence.right
+        );
+    }
+    if divergences.is_empty() {
+        println!("{} and {} agree on all {} operations", options.implementation, other, ops.len());
+        ExitCode::SUCCESS
+    } else {
+        println!("{} of {} operations diverged", divergences.len(), ops.len());
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,285 @@
+//! A `sixth::LinkedList` that never grows past a fixed capacity, for buffers of recent events.
+//!
+//! What happens when a push would overflow is up to the deque's `OverflowPolicy`. Elements it
+//! throws away are handed back from the push, or passed to an eviction callback if one is set.
+
+use alloc::boxed::Box;
+use core::error::Error;
+use core::fmt;
+use crate::sixth::{self, LinkedList};
+
+/// What a full `BoundedDeque` does with a push.
+///
+/// "Oldest" and "newest" are relative to the push: for `push_back` the oldest element is at
+/// the front, and for `push_front` it is at the back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the element at the far end to make room for the new one.
+    DropOldest,
+    /// Evict the new element itself, leaving the deque unchanged.
+    DropNewest,
+    /// Refuse the push with a `CapacityError` carrying the new element.
+    Reject
+}
+
+pub struct BoundedDeque<T> {
+    list: LinkedList<T>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    on_evict: Option<Box<dyn FnMut(T) + Send>>
+}
+
+impl<T> BoundedDeque<T> {
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "a bounded deque needs room for at least one element");
+        BoundedDeque { list: LinkedList::new(), capacity, policy, on_evict: None }
+    }
+
+    /// Sends evicted elements to `on_evict` instead of returning them from the push.
+    pub fn on_evict(mut self, on_evict: impl FnMut(T) + Send + 'static) -> Self {
+        self.on_evict = Some(Box::new(on_evict));
+        self
+    }
+
+    /// Pushes `element` onto the back, returning the evicted element if the deque was full
+    /// and the policy drops one. Evicted elements go to the callback instead, if there is one.
+    pub fn push_back(&mut self, element: T) -> Result<Option<T>, CapacityError<T>> {
+        self.push(element, true)
+    }
+
+    /// Pushes `element` onto the front; see `push_back`.
+    pub fn push_front(&mut self, element: T) -> Result<Option<T>, CapacityError<T>> {
+        self.push(element, false)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.list.front_mut()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.list.back_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.list.len() == self.capacity
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn iter(&self) -> sixth::Iter<'_, T> {
+        self.list.iter()
+    }
+
+    fn push(&mut self, element: T, back: bool) -> Result<Option<T>, CapacityError<T>> {
+        if !self.is_full() {
+            if back { self.list.push_back(element) } else { self.list.push_front(element) }
+            return Ok(None);
+        }
+
+        let evicted = match self.policy {
+            OverflowPolicy::DropOldest => {
+                let oldest = if back { self.list.pop_front() } else { self.list.pop_back() };
+                if back { self.list.push_back(element) } else { self.list.push_front(element) }
+                oldest.unwrap()
+            }
+            OverflowPolicy::DropNewest => element,
+            OverflowPolicy::Reject => return Err(CapacityError(element))
+        };
+        match &mut self.on_evict {
+            Some(on_evict) => {
+                on_evict(evicted);
+                Ok(None)
+            }
+            None => Ok(Some(evicted))
+        }
+    }
+}
+
+// Written by hand since the callback isn't `Debug`
+impl<T: fmt::Debug> fmt::Debug for BoundedDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundedDeque")
+            .field("elements", &DebugElements(&self.list))
+            .field("capacity", &self.capacity)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+struct DebugElements<'a, T>(&'a LinkedList<T>);
+
+impl<T: fmt::Debug> fmt::Debug for DebugElements<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedDeque<T> {
+    type Item = &'a T;
+    type IntoIter = sixth::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for BoundedDeque<T> {
+    type Item = T;
+    type IntoIter = sixth::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+/// A push refused by a full deque under `OverflowPolicy::Reject`, carrying the element back.
+pub struct CapacityError<T>(pub T);
+
+impl<T> CapacityError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CapacityError { .. }")
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("pushing onto a bounded deque that is full")
+    }
+}
+
+impl<T> Error for CapacityError<T> {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use super::{BoundedDeque, OverflowPolicy};
+
+    fn contents(deque: &BoundedDeque<i32>) -> Vec<i32> {
+        deque.iter().copied().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut deque = BoundedDeque::new(3, OverflowPolicy::Reject);
+        assert!(deque.is_empty());
+        assert_eq!(deque.push_back(2).unwrap(), None);
+        assert_eq!(deque.push_front(1).unwrap(), None);
+        assert_eq!(deque.push_back(3).unwrap(), None);
+        assert!(deque.is_full());
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert!(!deque.is_full());
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut deque = BoundedDeque::new(3, OverflowPolicy::DropOldest);
+        for i in 1..=3 {
+            deque.push_back(i).unwrap();
+        }
+        assert_eq!(deque.push_back(4).unwrap(), Some(1));
+        assert_eq!(deque.push_back(5).unwrap(), Some(2));
+        assert_eq!(contents(&deque), vec![3, 4, 5]);
+
+        // Pushing at the front evicts from the back
+        assert_eq!(deque.push_front(0).unwrap(), Some(5));
+        assert_eq!(contents(&deque), vec![0, 3, 4]);
+        assert_eq!(deque.len(), 3);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let mut deque = BoundedDeque::new(2, OverflowPolicy::DropNewest);
+        deque.push_back(1).unwrap();
+        deque.push_back(2).unwrap();
+        assert_eq!(deque.push_back(3).unwrap(), Some(3));
+        assert_eq!(deque.push_front(0).unwrap(), Some(0));
+        assert_eq!(contents(&deque), vec![1, 2]);
+
+        deque.pop_front();
+        assert_eq!(deque.push_back(3).unwrap(), None);
+        assert_eq!(contents(&deque), vec![2, 3]);
+    }
+
+    #[test]
+    fn reject() {
+        let mut deque = BoundedDeque::new(2, OverflowPolicy::Reject);
+        deque.push_back(1).unwrap();
+        deque.push_back(2).unwrap();
+
+        let error = deque.push_back(3).unwrap_err();
+        assert_eq!(error.to_string(), "pushing onto a bounded deque that is full");
+        assert_eq!(error.into_inner(), 3);
+        assert_eq!(deque.push_front(0).unwrap_err().0, 0);
+        assert_eq!(contents(&deque), vec![1, 2]);
+    }
+
+    #[test]
+    fn eviction_callback() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = evicted.clone();
+        let mut deque = BoundedDeque::new(2, OverflowPolicy::DropOldest)
+            .on_evict(move |element| sink.lock().unwrap().push(element));
+        for i in 1..=5 {
+            assert_eq!(deque.push_back(i).unwrap(), None);
+        }
+        assert_eq!(*evicted.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(contents(&deque), vec![4, 5]);
+
+        // Rejected elements come back in the error, not through the callback
+        let sink = evicted.clone();
+        let mut deque = BoundedDeque::new(1, OverflowPolicy::Reject)
+            .on_evict(move |element| sink.lock().unwrap().push(element));
+        deque.push_back(1).unwrap();
+        assert_eq!(deque.push_back(2).unwrap_err().0, 2);
+        assert_eq!(evicted.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        BoundedDeque::<i32>::new(0, OverflowPolicy::DropOldest);
+    }
+}
@@ -0,0 +1,420 @@
+//! A blocking multi-producer, single-consumer channel backed by a `fifth::List`.
+//!
+//! This is the `Mutex` + `Condvar` work queue we kept writing by hand. `channel()` is
+//! unbounded; `bounded(n)` makes senders wait whenever `n` elements are already queued.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use crate::fifth::List;
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: Option<usize>
+}
+
+struct State<T> {
+    queue: List<T>,
+    senders: usize,
+    receiver_alive: bool
+}
+
+impl<T> Shared<T> {
+    // Elements are never left half-moved while the lock is held, so a poisoned lock is
+    // still consistent and there's no reason to propagate the panic.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_full(&self, state: &State<T>) -> bool {
+        self.capacity.is_some_and(|capacity| state.queue.len() >= capacity)
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>
+}
+
+/// Creates a channel with no limit on how many elements may be queued.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    with_capacity(None)
+}
+
+/// Creates a channel that holds at most `capacity` elements; `send` blocks while it is full.
+///
+/// # Panics
+/// Panics if `capacity` is zero.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "a bounded channel needs room for at least one element");
+    with_capacity(Some(capacity))
+}
+
+fn with_capacity<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State { queue: List::new(), senders: 1, receiver_alive: true }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+impl<T> Sender<T> {
+    /// Queues `element`, waiting for room if the channel is bounded and full. Fails, handing
+    /// the element back, once the receiver has been dropped.
+    pub fn send(&self, element: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.lock();
+        while state.receiver_alive && self.shared.is_full(&state) {
+            state = self.shared.not_full.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+
+        if !state.receiver_alive {
+            return Err(SendError(element));
+        }
+
+        state.queue.push(element);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Waits for the next element. Fails once the channel is empty and every sender is gone.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(element) = self.take(&mut state) {
+                return Ok(element);
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = self.shared.not_empty.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+        match self.take(&mut state) {
+            Some(element) => Ok(element),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty)
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        // A deadline too far off to represent can never pass, so just wait
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.recv().map_err(|RecvError| RecvTimeoutError::Disconnected);
+        };
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(element) = self.take(&mut state) {
+                return Ok(element);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self.shared.not_empty.wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner).0;
+        }
+    }
+
+    /// Blocks on each element in turn until every sender has been dropped.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    fn take(&self, state: &mut State<T>) -> Option<T> {
+        let element = state.queue.pop();
+        if element.is_some() && self.shared.capacity.is_some() {
+            self.shared.not_full.notify_one();
+        }
+        element
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        // Nothing can receive these anymore, so don't keep them alive until the last sender goes
+        let queue = std::mem::take(&mut state.queue);
+        drop(state);
+        self.shared.not_full.notify_all();
+        drop(queue);
+    }
+}
+
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Returned by `send` when the receiver is gone, carrying the element that couldn't be sent.
+#[derive(PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// Written by hand so that `T` doesn't need to be `Debug`
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError { .. }")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a channel whose receiver has been dropped")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("receiving on an empty channel whose senders have all been dropped")
+    }
+}
+
+impl Error for RecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+            TryRecvError::Disconnected => RecvError.fmt(f)
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => f.write_str("timed out waiting on an empty channel"),
+            RecvTimeoutError::Disconnected => RecvError.fmt(f)
+        }
+    }
+}
+
+impl Error for RecvTimeoutError {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use super::{bounded, channel, RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+    #[test]
+    fn basics() {
+        let (sender, receiver) = channel();
+
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        sender.send(3).unwrap();
+
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(10)), Ok(3));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(10)), Err(RecvTimeoutError::Timeout));
+    }
+
+    #[test]
+    fn disconnect_after_last_sender() {
+        let (sender, receiver) = channel();
+        let other = sender.clone();
+
+        sender.send(1).unwrap();
+        drop(sender);
+        other.send(2).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(1));
+
+        // Elements still queued are delivered before the disconnection is reported
+        drop(other);
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn recv_timeout_without_a_deadline() {
+        let (sender, receiver) = channel();
+        sender.send(1).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::MAX), Ok(1));
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(2).unwrap();
+        });
+        assert_eq!(receiver.recv_timeout(Duration::MAX), Ok(2));
+        handle.join().unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::MAX), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn recv_wakes_on_disconnect() {
+        let (sender, receiver) = channel::<i32>();
+        let handle = thread::spawn(move || receiver.recv());
+        thread::sleep(Duration::from_millis(20));
+        drop(sender);
+        assert_eq!(handle.join().unwrap(), Err(RecvError));
+    }
+
+    #[test]
+    fn send_after_receiver_dropped() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        struct DropCounter(Arc<AtomicUsize>);
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let (sender, receiver) = channel();
+        sender.send(DropCounter(drops.clone())).unwrap();
+        drop(receiver);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+        let SendError(element) = sender.send(DropCounter(drops.clone())).unwrap_err();
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        drop(element);
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn bounded_backpressure() {
+        let (sender, receiver) = bounded(2);
+        let sent = Arc::new(AtomicUsize::new(0));
+
+        let thread_sent = sent.clone();
+        let handle = thread::spawn(move || {
+            for i in 0..5 {
+                sender.send(i).unwrap();
+                thread_sent.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        // The sender can only get ahead of us by the channel's capacity
+        while sent.load(Ordering::SeqCst) < 2 {
+            thread::yield_now();
+        }
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        for i in 0..5 {
+            assert_eq!(receiver.recv(), Ok(i));
+        }
+        handle.join().unwrap();
+        assert_eq!(receiver.recv(), Err(RecvError));
+    }
+
+    #[test]
+    fn bounded_sender_unblocks_on_receiver_drop() {
+        let (sender, receiver) = bounded(1);
+        sender.send(1).unwrap();
+
+        let handle = thread::spawn(move || sender.send(2));
+        thread::sleep(Duration::from_millis(20));
+        drop(receiver);
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        let _ = bounded::<i32>(0);
+    }
+
+    #[test]
+    fn multiple_producers() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 1_000;
+
+        let (sender, receiver) = bounded(16);
+        let handles: Vec<_> = (0..PRODUCERS).map(|producer| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    sender.send((producer, i)).unwrap();
+                }
+            })
+        }).collect();
+        drop(sender);
+
+        let mut next = [0; PRODUCERS];
+        for (producer, i) in &receiver {
+            assert_eq!(next[producer], i);
+            next[producer] += 1;
+        }
+        assert_eq!(next, [PER_PRODUCER; PRODUCERS]);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
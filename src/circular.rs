@@ -0,0 +1,495 @@
+//! A ring of `sixth` nodes with no ends: the tail's `next` is the head and the head's
+//! `previous` is the tail, so rotating is just moving the head pointer.
+//!
+//! The ring still has a front, the element `head` points at, which is where `iter` starts
+//! and what `rotate_forward` moves past.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use crate::allocator::{self, Global};
+use crate::sixth::{self, Link, Node};
+
+pub struct CircularList<T> {
+    head: Link<T>,
+    length: usize,
+    _boo: PhantomData<T>
+}
+
+unsafe impl<T: Send> Send for CircularList<T> {}
+unsafe impl<T: Sync> Sync for CircularList<T> {}
+
+impl<T> CircularList<T> {
+    pub fn new() -> Self {
+        CircularList { head: None, length: 0, _boo: PhantomData }
+    }
+
+    pub fn push_front(&mut self, element: T) {
+        let node = self.link_before_head(element);
+        self.head = Some(node);
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        let node = self.link_before_head(element);
+        // Linking in front of the head of a ring is the same as linking after its tail
+        if self.length == 1 {
+            self.head = Some(node);
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| unsafe { self.unlink(head) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail().map(|tail| unsafe { self.unlink(tail) })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|head| unsafe { &(*head.as_ptr()).element })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|head| unsafe { &mut (*head.as_ptr()).element })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail().map(|tail| unsafe { &(*tail.as_ptr()).element })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail().map(|tail| unsafe { &mut (*tail.as_ptr()).element })
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Moves the front `n` elements to the back, so `[1, 2, 3]` rotated forward by one
+    /// becomes `[2, 3, 1]`. Walks whichever way round the ring is shorter.
+    pub fn rotate_forward(&mut self, n: usize) {
+        if self.length == 0 {
+            return;
+        }
+        let n = n % self.length;
+        if n <= self.length / 2 {
+            self.step(n, true);
+        } else {
+            self.step(self.length - n, false);
+        }
+    }
+
+    /// Moves the back `n` elements to the front, undoing `rotate_forward(n)`.
+    pub fn rotate_backward(&mut self, n: usize) {
+        if self.length == 0 {
+            return;
+        }
+        self.rotate_forward(self.length - n % self.length);
+    }
+
+    /// Visits each element once, from the front round to the back.
+    pub fn iter(&self) -> sixth::Iter<'_, T> {
+        unsafe { sixth::Iter::new(self.head, self.tail(), self.length) }
+    }
+
+    /// Goes round the ring forever, starting at the front. Yields nothing if the list is empty.
+    pub fn cycle_iter(&self) -> CycleIter<'_, T> {
+        CycleIter { current: self.head, _boo: PhantomData }
+    }
+
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor { current: self.head, _boo: PhantomData }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.head, list: self }
+    }
+
+    fn tail(&self) -> Link<T> {
+        self.head.map(|head| unsafe { (*head.as_ptr()).previous.unwrap() })
+    }
+
+    fn step(&mut self, n: usize, forward: bool) {
+        for _ in 0..n {
+            self.head = self.head.map(|head| unsafe {
+                let head = &*head.as_ptr();
+                if forward { head.next.unwrap() } else { head.previous.unwrap() }
+            });
+        }
+    }
+
+    // Links a new node in between the tail and the head, without moving the head
+    fn link_before_head(&mut self, element: T) -> NonNull<Node<T>> {
+        let node = allocator::allocate_node(&Global, Node { previous: None, element, next: None });
+        unsafe {
+            let (previous, next) = match self.head {
+                Some(head) => ((*head.as_ptr()).previous.unwrap(), head),
+                None => (node, node)
+            };
+            (*node.as_ptr()).previous = Some(previous);
+            (*node.as_ptr()).next = Some(next);
+            (*previous.as_ptr()).next = Some(node);
+            (*next.as_ptr()).previous = Some(node);
+        }
+        self.length += 1;
+        node
+    }
+
+    // Unlinks `node`, moving the head on if it was the head, and frees it
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        let previous = (*node.as_ptr()).previous.unwrap();
+        let next = (*node.as_ptr()).next.unwrap();
+        (*previous.as_ptr()).next = Some(next);
+        (*next.as_ptr()).previous = Some(previous);
+
+        self.length -= 1;
+        if self.length == 0 {
+            self.head = None;
+        } else if self.head == Some(node) {
+            self.head = Some(next);
+        }
+
+        let element = ptr::addr_of!((*node.as_ptr()).element).read();
+        allocator::deallocate_node(&Global, node);
+        element
+    }
+}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for CircularList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CircularList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Extend<T> for CircularList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = CircularList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CircularList<T> {
+    type Item = &'a T;
+    type IntoIter = sixth::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T> {
+    list: CircularList<T>
+}
+
+impl<T> IntoIterator for CircularList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.length, Some(self.list.length))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+pub struct CycleIter<'a, T> {
+    current: Link<T>,
+    _boo: PhantomData<&'a T>
+}
+
+impl<'a, T> Iterator for CycleIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.current.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            self.current = node.next;
+            &node.element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.current {
+            Some(_) => (usize::MAX, None),
+            None => (0, Some(0))
+        }
+    }
+}
+
+/// A position in the ring. Moving past the back wraps round to the front and vice versa.
+pub struct Cursor<'a, T> {
+    current: Link<T>,
+    _boo: PhantomData<&'a T>
+}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn current(&self) -> Option<&'a T> {
+        self.current.map(|node| unsafe { &(*node.as_ptr()).element })
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.current.and_then(|node| unsafe { (*node.as_ptr()).next });
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.current.and_then(|node| unsafe { (*node.as_ptr()).previous });
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.current.map(|node| unsafe { &(*(*node.as_ptr()).next.unwrap().as_ptr()).element })
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        self.current.map(|node| unsafe { &(*(*node.as_ptr()).previous.unwrap().as_ptr()).element })
+    }
+}
+
+/// A position in the ring that can edit it. The cursor only points at nothing while the list
+/// is empty.
+pub struct CursorMut<'a, T> {
+    list: &'a mut CircularList<T>,
+    current: Link<T>
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current.map(|node| unsafe { &mut (*node.as_ptr()).element })
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.current.and_then(|node| unsafe { (*node.as_ptr()).next });
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.current.and_then(|node| unsafe { (*node.as_ptr()).previous });
+    }
+
+    /// Inserts `element` after the cursor, or as the only element if the list is empty.
+    pub fn insert_after(&mut self, element: T) {
+        match self.current {
+            Some(current) => unsafe {
+                // Linking in before the next node, with the head pointed there for a moment
+                let head = self.list.head;
+                self.list.head = (*current.as_ptr()).next;
+                self.list.link_before_head(element);
+                self.list.head = head;
+            },
+            None => {
+                self.list.push_back(element);
+                self.current = self.list.head;
+            }
+        }
+    }
+
+    /// Inserts `element` before the cursor, or as the only element if the list is empty.
+    /// Inserting before the front makes the new element the back, not the front.
+    pub fn insert_before(&mut self, element: T) {
+        match self.current {
+            Some(current) => {
+                let head = self.list.head;
+                self.list.head = Some(current);
+                self.list.link_before_head(element);
+                self.list.head = head;
+            }
+            None => {
+                self.list.push_back(element);
+                self.current = self.list.head;
+            }
+        }
+    }
+
+    /// Removes the current element and moves the cursor on to the one after it.
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.current.map(|node| unsafe {
+            self.current = (*node.as_ptr()).next.filter(|&next| next != node);
+            self.list.unlink(node)
+        })
+    }
+
+    /// Makes the current element the front of the list.
+    pub fn set_front(&mut self) {
+        self.list.head = self.current;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CircularList;
+
+    fn contents(list: &CircularList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = CircularList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(contents(&list), vec![1, 2, 3]);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+
+        *list.front_mut().unwrap() = 10;
+        *list.back_mut().unwrap() = 30;
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn tail_links_to_head() {
+        let list: CircularList<i32> = (1..=4).collect();
+        let head = list.head.unwrap();
+        unsafe {
+            let tail = (*head.as_ptr()).previous.unwrap();
+            assert_eq!((*tail.as_ptr()).element, 4);
+            assert_eq!((*tail.as_ptr()).next, Some(head));
+        }
+    }
+
+    #[test]
+    fn rotate() {
+        let mut list: CircularList<i32> = (1..=5).collect();
+        list.rotate_forward(1);
+        assert_eq!(contents(&list), vec![2, 3, 4, 5, 1]);
+        list.rotate_backward(1);
+        assert_eq!(contents(&list), vec![1, 2, 3, 4, 5]);
+
+        // Rotations wrap and may take the short way round
+        list.rotate_forward(4);
+        assert_eq!(contents(&list), vec![5, 1, 2, 3, 4]);
+        list.rotate_forward(12);
+        assert_eq!(contents(&list), vec![2, 3, 4, 5, 1]);
+        list.rotate_backward(7);
+        assert_eq!(contents(&list), vec![5, 1, 2, 3, 4]);
+        list.rotate_forward(5);
+        assert_eq!(contents(&list), vec![5, 1, 2, 3, 4]);
+
+        let mut empty: CircularList<i32> = CircularList::new();
+        empty.rotate_forward(3);
+        empty.rotate_backward(3);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn iter() {
+        let list: CircularList<i32> = (1..=3).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        let cycled: Vec<_> = list.cycle_iter().take(7).copied().collect();
+        assert_eq!(cycled, vec![1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(CircularList::<i32>::new().cycle_iter().next(), None);
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(3));
+        assert_eq!(into_iter.collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn cursor_wraps() {
+        let list: CircularList<i32> = (1..=3).collect();
+        let mut cursor = list.cursor();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&3));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&3));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&2));
+        assert_eq!(cursor.peek_next(), Some(&3));
+
+        let empty: CircularList<i32> = CircularList::new();
+        let mut cursor = empty.cursor();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut list: CircularList<i32> = (1..=3).collect();
+        let mut cursor = list.cursor_mut();
+        cursor.move_prev();
+        cursor.insert_after(4);
+        cursor.insert_before(0);
+        *cursor.current().unwrap() *= 10;
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 4));
+        cursor.set_front();
+        assert_eq!(contents(&list), vec![4, 1, 2, 0, 30]);
+
+        let mut empty = CircularList::new();
+        let mut cursor = empty.cursor_mut();
+        cursor.insert_before(7);
+        assert_eq!(cursor.current(), Some(&mut 7));
+        assert_eq!(contents(&empty), vec![7]);
+    }
+
+    #[test]
+    fn round_robin() {
+        // Each task needs a number of turns; finished tasks leave the ring
+        let mut tasks: CircularList<(char, u32)> = [('a', 1), ('b', 3), ('c', 2)].into_iter().collect();
+        let mut order = String::new();
+        let mut cursor = tasks.cursor_mut();
+        while let Some((name, turns)) = cursor.current() {
+            order.push(*name);
+            *turns -= 1;
+            if *turns == 0 {
+                cursor.remove_current();
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert_eq!(order, "abcbcb");
+        assert!(tasks.is_empty());
+    }
+}
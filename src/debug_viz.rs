@@ -0,0 +1,374 @@
+//! Renders the lists' actual node graphs as Graphviz DOT, for looking at pointer bugs.
+//!
+//! Each node is drawn as a record of its address, its element's `Debug` output, and for the
+//! `Rc`-based lists its strong count. `next` edges are solid and `previous` edges dashed.
+//! Several lists can go into one `Graph`; nodes are keyed by address, so lists that share
+//! nodes, like `third::List` tails, show up as branches merging into one suffix.
+//!
+//! `ToDot` is implemented here for `first` through `sixth`. Any other list can implement it
+//! with `Graph`'s drawing methods.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use core::fmt::{self, Debug, Write};
+use crate::allocator::Allocator;
+use crate::{fifth, first, fourth, second, sixth, third};
+
+/// A list whose nodes can be drawn into a `Graph`.
+pub trait ToDot {
+    /// Adds every node and link of the list to `graph`, labelling its ends with `name`.
+    fn add_to_graph(&self, graph: &mut Graph, name: &str);
+}
+
+/// Renders a single list as a standalone DOT document.
+pub fn to_dot<L: ToDot + ?Sized>(list: &L) -> String {
+    let mut graph = Graph::new();
+    graph.add("list", list);
+    graph.finish()
+}
+
+/// A DOT document under construction.
+#[derive(Default)]
+pub struct Graph {
+    body: String,
+    seen: BTreeSet<usize>
+}
+
+/// Which link an edge stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Next,
+    Previous
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `list` under `name`; calling this again with another list merges any shared nodes.
+    pub fn add<L: ToDot + ?Sized>(&mut self, name: &str, list: &L) -> &mut Self {
+        list.add_to_graph(self, name);
+        self
+    }
+
+    pub fn finish(&self) -> String {
+        format!("digraph lists {{\n    rankdir=LR;\n    node [shape=record];\n{}}}\n", self.body)
+    }
+
+    /// Draws a node unless one at `address` is already drawn. Returns whether it was new, so
+    /// callers walking a shared suffix can stop once they reach nodes another list drew.
+    pub fn node<P: ?Sized>(
+        &mut self,
+        address: *const P,
+        element: &dyn Debug,
+        strong_count: Option<usize>
+    ) -> bool {
+        let address = address as *const ();
+        if !self.seen.insert(address as usize) {
+            return false;
+        }
+        let mut label = format!("{:p}|{}", address, escape(&format!("{:?}", element)));
+        if let Some(count) = strong_count {
+            write!(label, "|rc={}", count).unwrap();
+        }
+        writeln!(self.body, "    \"{:p}\" [label=\"{{{}}}\"];", address, label).unwrap();
+        true
+    }
+
+    /// Draws a link from the node at `from` to the node at `to`.
+    pub fn edge<P: ?Sized, Q: ?Sized>(&mut self, from: *const P, to: *const Q, edge: Edge) {
+        let style = match edge {
+            Edge::Next => "label=\"next\"",
+            Edge::Previous => "label=\"previous\", style=dashed"
+        };
+        writeln!(self.body, "    \"{:p}\" -> \"{:p}\" [{}];", from as *const (), to as *const (), style).unwrap();
+    }
+
+    /// Draws a `name.end` marker pointing at `target`, or at a null box for an empty end.
+    pub fn marker<P: ?Sized>(&mut self, name: &str, end: &str, target: Option<*const P>) {
+        let marker = format!("{}.{}", name, end);
+        writeln!(self.body, "    \"{}\" [shape=plaintext];", escape(&marker)).unwrap();
+        match target {
+            Some(target) => {
+                writeln!(self.body, "    \"{}\" -> \"{:p}\";", escape(&marker), target as *const ()).unwrap();
+            }
+            None => {
+                writeln!(self.body, "    \"{}.null\" [shape=point];", escape(&marker)).unwrap();
+                writeln!(self.body, "    \"{0}\" -> \"{0}.null\";", escape(&marker)).unwrap();
+            }
+        }
+    }
+}
+
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.finish())
+    }
+}
+
+// Backslash-escapes everything that means something inside a quoted record label
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '"' | '\\' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl<T: Debug> ToDot for first::List<T> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        fn as_node<T>(link: &first::Link<T>) -> Option<&first::Node<T>> {
+            match link {
+                first::Link::More(boxed_node) => Some(boxed_node),
+                first::Link::Empty => None
+            }
+        }
+
+        let mut current = as_node(&self.head);
+        graph.marker(name, "head", current.map(|node| node as *const first::Node<T>));
+        while let Some(node) = current {
+            graph.node(node, &node.element, None);
+            current = as_node(&node.next);
+            if let Some(next) = current {
+                graph.edge(node, next, Edge::Next);
+            }
+        }
+    }
+}
+
+impl<T: Debug> ToDot for second::List<T> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        let mut current = self.head.as_deref();
+        graph.marker(name, "head", current.map(|node| node as *const second::Node<T>));
+        while let Some(node) = current {
+            graph.node(node, &node.element, None);
+            current = node.next.as_deref();
+            if let Some(next) = current {
+                graph.edge(node, next, Edge::Next);
+            }
+        }
+    }
+}
+
+impl<T: Debug> ToDot for third::List<T> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        graph.marker(name, "head", self.head.as_ref().map(Rc::as_ptr));
+        let mut current = self.head.as_ref();
+        while let Some(rc_node) = current {
+            // Stop at the first node another list already drew; the rest of the suffix is shared
+            if !graph.node(Rc::as_ptr(rc_node), &rc_node.element, Some(Rc::strong_count(rc_node))) {
+                break;
+            }
+            current = rc_node.next.as_ref();
+            if let Some(next) = current {
+                graph.edge(Rc::as_ptr(rc_node), Rc::as_ptr(next), Edge::Next);
+            }
+        }
+    }
+}
+
+impl<T: Debug> ToDot for fourth::List<T> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        graph.marker(name, "head", self.head.as_ref().map(Rc::as_ptr));
+        graph.marker(name, "tail", self.tail.as_ref().map(Rc::as_ptr));
+        let mut current = self.head.clone();
+        while let Some(rc_node) = current {
+            let node = rc_node.borrow();
+            // Our own `current` handle isn't part of the structure, so leave it out of the count.
+            // A node that's already drawn means a corrupted `next` cycle, so stop there.
+            if !graph.node(Rc::as_ptr(&rc_node), &node.element, Some(Rc::strong_count(&rc_node) - 1)) {
+                break;
+            }
+            if let Some(next) = &node.next {
+                graph.edge(Rc::as_ptr(&rc_node), Rc::as_ptr(next), Edge::Next);
+            }
+            if let Some(previous) = &node.previous {
+                graph.edge(Rc::as_ptr(&rc_node), Rc::as_ptr(previous), Edge::Previous);
+            }
+            current = node.next.clone();
+        }
+    }
+}
+
+impl<T: Debug, A: Allocator> ToDot for fifth::List<T, A> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        graph.marker(name, "head", (!self.head.is_null()).then_some(self.head as *const fifth::Node<T>));
+        graph.marker(name, "tail", (!self.tail.is_null()).then_some(self.tail as *const fifth::Node<T>));
+        let mut current = self.head;
+        while !current.is_null() {
+            unsafe {
+                if !graph.node(current, &(*current).element, None) {
+                    break;
+                }
+                let next = (*current).next;
+                if !next.is_null() {
+                    graph.edge(current, next, Edge::Next);
+                }
+                current = next;
+            }
+        }
+    }
+}
+
+impl<T: Debug, A: Allocator> ToDot for sixth::LinkedList<T, A> {
+    fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+        graph.marker(name, "head", self.head_node().map(|head| head.as_ptr() as *const sixth::Node<T>));
+        graph.marker(name, "tail", self.tail_node().map(|tail| tail.as_ptr() as *const sixth::Node<T>));
+        let mut current = self.head_node();
+        while let Some(node) = current {
+            unsafe {
+                let node_ref = &*node.as_ptr();
+                if !graph.node(node.as_ptr(), &node_ref.element, None) {
+                    break;
+                }
+                if let Some(next) = node_ref.next {
+                    graph.edge(node.as_ptr(), next.as_ptr(), Edge::Next);
+                }
+                if let Some(previous) = node_ref.previous {
+                    graph.edge(node.as_ptr(), previous.as_ptr(), Edge::Previous);
+                }
+                current = node_ref.next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, to_dot, Edge, Graph, ToDot};
+    use crate::{fifth, first, fourth, second, sixth, third};
+
+    // The quoted node ids in `dot`, in order of appearance, without markers
+    fn node_ids(dot: &str) -> Vec<&str> {
+        dot.lines()
+            .filter(|line| line.contains("[label=\"{"))
+            .map(|line| line.trim().split('"').nth(1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn escapes_record_syntax() {
+        assert_eq!(escape(r#"{"a"|<b>}\"#), r#"\{\"a\"\|\<b\>\}\\"#);
+    }
+
+    #[test]
+    fn singly_linked() {
+        let mut list = first::List::new();
+        list.push(1);
+        list.push(2);
+        let dot = to_dot(&list);
+        assert!(dot.starts_with("digraph lists {"));
+        assert_eq!(node_ids(&dot).len(), 2);
+        assert_eq!(dot.matches("[label=\"next\"]").count(), 1);
+        assert!(dot.contains("|2}\"]"));
+        assert!(dot.contains("\"list.head\" -> \"0x"));
+
+        let mut list = second::List::new();
+        list.push("a \"quoted\" {record}");
+        let dot = to_dot(&list);
+        assert!(dot.contains(r#"|\"a \\\"quoted\\\" \{record\}\"}"#));
+
+        let empty: fifth::List<i32> = fifth::List::new();
+        let dot = to_dot(&empty);
+        assert!(dot.contains("\"list.head\" -> \"list.head.null\""));
+        assert!(dot.contains("\"list.tail\" -> \"list.tail.null\""));
+    }
+
+    #[test]
+    fn doubly_linked() {
+        let mut list = sixth::LinkedList::new();
+        for i in 0..3 {
+            list.push_back(i);
+        }
+        let dot = to_dot(&list);
+        let ids = node_ids(&dot);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(dot.matches("[label=\"next\"]").count(), 2);
+        assert_eq!(dot.matches("style=dashed").count(), 2);
+        assert!(dot.contains(&format!("\"list.head\" -> \"{}\"", ids[0])));
+        assert!(dot.contains(&format!("\"list.tail\" -> \"{}\"", ids[2])));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"previous\", style=dashed]", ids[1], ids[0])));
+
+        // Inner nodes of `fourth` are held by both neighbours
+        let mut list = fourth::List::new();
+        for i in 0..3 {
+            list.push_tail(i);
+        }
+        let dot = to_dot(&list);
+        assert!(dot.contains("|0|rc=2}"));
+        assert!(dot.contains("|1|rc=2}"));
+        assert!(dot.contains("|2|rc=2}"));
+        assert_eq!(dot.matches("style=dashed").count(), 2);
+    }
+
+    #[test]
+    fn shared_suffix_merges() {
+        let base = third::List::new().prepend(1).prepend(2);
+        let left = base.prepend(3);
+        let right = base.prepend(4);
+
+        let mut graph = Graph::new();
+        graph.add("left", &left).add("right", &right);
+        let dot = graph.to_string();
+
+        // Two heads plus the two shared nodes, each drawn once
+        assert_eq!(node_ids(&dot).len(), 4);
+        assert!(dot.contains("|2|rc=3}"));
+        assert!(dot.contains("|1|rc=1}"));
+        // Both branches point into the same node for `2`
+        let shared = dot.lines().find(|line| line.contains("|2|rc=3}")).unwrap().trim().split('"').nth(1).unwrap();
+        assert_eq!(dot.matches(&format!("-> \"{}\" [label=\"next\"]", shared)).count(), 2);
+    }
+
+    #[test]
+    fn fourth_stops_at_a_next_cycle() {
+        let mut list = fourth::List::new();
+        for i in 0..3 {
+            list.push_tail(i);
+        }
+        let head = list.head.clone();
+        list.tail.as_ref().unwrap().borrow_mut().next = head;
+        assert_eq!(node_ids(&to_dot(&list)).len(), 3);
+        list.tail.as_ref().unwrap().borrow_mut().next = None;
+    }
+
+    #[test]
+    fn sixth_stops_at_a_next_cycle() {
+        let mut list = sixth::LinkedList::new();
+        for i in 0..3 {
+            list.push_back(i);
+        }
+        let tail = list.tail_node().unwrap();
+        unsafe {
+            (*tail.as_ptr()).next = list.head_node();
+            assert_eq!(node_ids(&to_dot(&list)).len(), 3);
+            (*tail.as_ptr()).next = None;
+        }
+    }
+
+    #[test]
+    fn implementable_outside_the_crate() {
+        // A list drawn only through `Graph`'s public methods, as a downstream crate would
+        struct Pair([u8; 2]);
+
+        impl ToDot for Pair {
+            fn add_to_graph(&self, graph: &mut Graph, name: &str) {
+                graph.marker(name, "head", Some(&self.0[0] as *const u8));
+                graph.node(&self.0[0], &self.0[0], None);
+                graph.node(&self.0[1], &self.0[1], None);
+                graph.edge(&self.0[0], &self.0[1], Edge::Next);
+            }
+        }
+
+        let dot = to_dot(&Pair([7, 8]));
+        assert_eq!(node_ids(&dot).len(), 2);
+        assert_eq!(dot.matches("[label=\"next\"]").count(), 1);
+        assert!(dot.contains("|8}\"]"));
+    }
+}
@@ -0,0 +1,370 @@
+//! A small epoch-based reclamation scheme for the lock-free structures in this crate.
+//!
+//! Threads `pin()` themselves before touching shared nodes. A node that has been unlinked
+//! is handed to `Guard::defer_destroy` and only freed once the global epoch has moved on
+//! twice, at which point no pinned thread can still be holding a pointer to it.
+
+use std::cell::{Cell, UnsafeCell};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+// The low bit of a participant's state marks it as pinned, so epochs advance in steps of 2.
+const PINNED: usize = 1;
+const EPOCH_STEP: usize = 2;
+
+// How many deferred frees a thread buffers before it tries to reclaim some of them.
+const COLLECT_THRESHOLD: usize = 64;
+
+// How many pins a thread makes between attempts to advance the epoch.
+const PINS_BETWEEN_COLLECT: usize = 128;
+
+struct Deferred {
+    ptr: *mut u8,
+    free: unsafe fn(*mut u8),
+    epoch: usize
+}
+
+// A deferred free is only ever run once, by whichever thread ends up collecting it.
+unsafe impl Send for Deferred {}
+
+impl Deferred {
+    fn is_expired(&self, global_epoch: usize) -> bool {
+        global_epoch.wrapping_sub(self.epoch) >= 2 * EPOCH_STEP
+    }
+}
+
+struct Local {
+    state: AtomicUsize,
+    in_use: AtomicBool,
+    next: *mut Local,
+    // Only touched by the thread that currently owns this record
+    guards: Cell<usize>,
+    pins: Cell<usize>,
+    bag: UnsafeCell<Vec<Deferred>>
+}
+
+unsafe impl Sync for Local {}
+
+struct Global {
+    epoch: AtomicUsize,
+    locals: AtomicPtr<Local>,
+    orphans: Mutex<Vec<Deferred>>
+}
+
+static GLOBAL: Global = Global {
+    epoch: AtomicUsize::new(0),
+    locals: AtomicPtr::new(ptr::null_mut()),
+    orphans: Mutex::new(Vec::new())
+};
+
+impl Global {
+    // Participant records are never freed; a thread that exits gives its record back so that
+    // a later thread can claim it instead of growing the list.
+    fn register(&self) -> *const Local {
+        let mut current = self.locals.load(Ordering::Acquire);
+        while let Some(local) = unsafe { current.as_ref() } {
+            if local.in_use.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                return local;
+            }
+            current = local.next;
+        }
+
+        let local = Box::into_raw(Box::new(Local {
+            state: AtomicUsize::new(0),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+            guards: Cell::new(0),
+            pins: Cell::new(0),
+            bag: UnsafeCell::new(Vec::new())
+        }));
+        loop {
+            let head = self.locals.load(Ordering::Relaxed);
+            unsafe { (*local).next = head; }
+            if self.locals.compare_exchange(head, local, Ordering::Release, Ordering::Relaxed).is_ok() {
+                return local;
+            }
+        }
+    }
+
+    // Moves the global epoch forward if every pinned thread has observed the current one.
+    // Returns whichever epoch is current afterwards.
+    fn try_advance(&self) -> usize {
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+
+        let mut current = self.locals.load(Ordering::Acquire);
+        while let Some(local) = unsafe { current.as_ref() } {
+            let state = local.state.load(Ordering::Relaxed);
+            if state & PINNED == PINNED && state & !PINNED != epoch {
+                return epoch;
+            }
+            current = local.next;
+        }
+        fence(Ordering::Acquire);
+
+        let new_epoch = epoch.wrapping_add(EPOCH_STEP);
+        match self.epoch.compare_exchange(epoch, new_epoch, Ordering::Release, Ordering::Relaxed) {
+            Ok(_) => new_epoch,
+            Err(current) => current
+        }
+    }
+}
+
+impl Local {
+    fn pin(&self) {
+        let guards = self.guards.get();
+        self.guards.set(guards + 1);
+        if guards == 0 {
+            let epoch = GLOBAL.epoch.load(Ordering::Relaxed);
+            self.state.store(epoch | PINNED, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+
+            let pins = self.pins.get().wrapping_add(1);
+            self.pins.set(pins);
+            if pins.is_multiple_of(PINS_BETWEEN_COLLECT) {
+                self.collect();
+            }
+        }
+    }
+
+    fn unpin(&self) {
+        let guards = self.guards.get() - 1;
+        self.guards.set(guards);
+        if guards == 0 {
+            self.state.store(0, Ordering::Release);
+        }
+    }
+
+    fn defer(&self, deferred: Deferred) {
+        let bag_len = unsafe {
+            let bag = &mut *self.bag.get();
+            bag.push(deferred);
+            bag.len()
+        };
+        if bag_len >= COLLECT_THRESHOLD {
+            self.collect();
+        }
+    }
+
+    fn collect(&self) {
+        let epoch = GLOBAL.try_advance();
+
+        // The bag is taken out while the frees run so nothing can observe it half-drained.
+        // A thread's own bag is in epoch order, so only the expired prefix needs visiting;
+        // otherwise a stalled epoch would make every defer rescan the whole bag.
+        let mut bag = unsafe { mem::take(&mut *self.bag.get()) };
+        let expired = bag.iter().take_while(|deferred| deferred.is_expired(epoch)).count();
+        for deferred in bag.drain(..expired) {
+            unsafe { (deferred.free)(deferred.ptr) };
+        }
+        // A free may have deferred more garbage; it's newer than the leftovers, so it goes last
+        unsafe {
+            let mut newer = mem::replace(&mut *self.bag.get(), bag);
+            (*self.bag.get()).append(&mut newer);
+        }
+
+        // Garbage left behind by exited threads is reclaimed opportunistically
+        if let Ok(mut orphans) = GLOBAL.orphans.try_lock() {
+            reclaim(&mut orphans, epoch);
+        }
+    }
+}
+
+fn reclaim(bag: &mut Vec<Deferred>, global_epoch: usize) {
+    bag.retain(|deferred| {
+        if deferred.is_expired(global_epoch) {
+            unsafe { (deferred.free)(deferred.ptr) };
+            false
+        } else {
+            true
+        }
+    });
+}
+
+struct Handle {
+    local: *const Local
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let local = unsafe { &*self.local };
+        local.collect();
+
+        let bag = unsafe { mem::take(&mut *local.bag.get()) };
+        if !bag.is_empty() {
+            GLOBAL.orphans.lock().unwrap_or_else(PoisonError::into_inner).extend(bag);
+        }
+        local.in_use.store(false, Ordering::Release);
+    }
+}
+
+thread_local! {
+    static HANDLE: Handle = Handle { local: GLOBAL.register() };
+}
+
+/// Keeps the current thread pinned to an epoch. Nodes loaded while a `Guard` is alive
+/// stay allocated until it is dropped.
+pub(crate) struct Guard {
+    local: *const Local
+}
+
+pub(crate) fn pin() -> Guard {
+    HANDLE.with(|handle| {
+        unsafe { (*handle.local).pin(); }
+        Guard { local: handle.local }
+    })
+}
+
+impl Guard {
+    /// Frees `ptr` as a `Box<N>` once no thread can still be reading it.
+    ///
+    /// # Safety
+    /// `ptr` must come from `Box::into_raw`, must already be unreachable for threads that pin
+    /// after this call, and must not be deferred twice.
+    pub(crate) unsafe fn defer_destroy<N>(&self, ptr: *mut N) {
+        unsafe fn free<N>(ptr: *mut u8) {
+            drop(Box::from_raw(ptr as *mut N));
+        }
+
+        fence(Ordering::SeqCst);
+        let epoch = GLOBAL.epoch.load(Ordering::Relaxed);
+        (*self.local).defer(Deferred { ptr: ptr as *mut u8, free: free::<N>, epoch });
+    }
+
+    /// Tries to advance the epoch and free whatever has expired.
+    #[cfg(test)]
+    pub(crate) fn flush(&self) {
+        unsafe { (*self.local).collect(); }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        unsafe { (*self.local).unpin(); }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use super::{pin, GLOBAL, HANDLE};
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn deferred_destroy_runs_after_unpin() {
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        {
+            let guard = pin();
+            let ptr = Box::into_raw(Box::new(DropCounter(drops.clone())));
+            unsafe { guard.defer_destroy(ptr); }
+            // We are still pinned in the retiring epoch, so nothing may be freed yet
+            guard.flush();
+            assert_eq!(drops.load(Ordering::SeqCst), 0);
+        }
+
+        // Other tests may be pinned concurrently, so give them a chance to move on
+        for _ in 0..100_000 {
+            if drops.load(Ordering::SeqCst) == 1 {
+                break;
+            }
+            pin().flush();
+            thread::yield_now();
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn nested_guards() {
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let outer = pin();
+        {
+            let inner = pin();
+            let ptr = Box::into_raw(Box::new(DropCounter(drops.clone())));
+            unsafe { inner.defer_destroy(ptr); }
+        }
+        // Dropping the inner guard must not unpin the thread
+        outer.flush();
+        outer.flush();
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn exited_threads_hand_off_garbage() {
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let thread_drops = drops.clone();
+        thread::spawn(move || {
+            let guard = pin();
+            for _ in 0..10 {
+                let ptr = Box::into_raw(Box::new(DropCounter(thread_drops.clone())));
+                unsafe { guard.defer_destroy(ptr); }
+            }
+        }).join().unwrap();
+
+        for _ in 0..100_000 {
+            if drops.load(Ordering::SeqCst) == 10 {
+                break;
+            }
+            pin().flush();
+            thread::yield_now();
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 10);
+    }
+
+    // Defers another drop counter from inside its own destructor
+    struct Redefer(Arc<AtomicUsize>);
+
+    impl Drop for Redefer {
+        fn drop(&mut self) {
+            let ptr = Box::into_raw(Box::new(DropCounter(self.0.clone())));
+            unsafe { pin().defer_destroy(ptr); }
+        }
+    }
+
+    fn bag_epochs() -> Vec<usize> {
+        HANDLE.with(|handle| unsafe {
+            (*(*handle.local).bag.get()).iter().map(|deferred| deferred.epoch).collect()
+        })
+    }
+
+    #[test]
+    fn defers_during_collect_keep_the_bag_in_order() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let ptr = Box::into_raw(Box::new(Redefer(drops.clone())));
+        unsafe { pin().defer_destroy(ptr); }
+
+        // Retire something younger once the epoch has moved, so it's still pending when the
+        // redeferring drop runs
+        let start = GLOBAL.epoch.load(Ordering::SeqCst);
+        while GLOBAL.epoch.load(Ordering::SeqCst) == start {
+            pin().flush();
+            thread::yield_now();
+        }
+        let ptr = Box::into_raw(Box::new(DropCounter(drops.clone())));
+        unsafe { pin().defer_destroy(ptr); }
+
+        for _ in 0..100_000 {
+            if drops.load(Ordering::SeqCst) == 2 {
+                break;
+            }
+            pin().flush();
+            let epochs = bag_epochs();
+            assert!(epochs.windows(2).all(|pair| pair[0] <= pair[1]), "bag out of order: {:?}", epochs);
+            thread::yield_now();
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+    }
+}
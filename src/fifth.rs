@@ -1,27 +1,70 @@
-use std::ptr;
-
-pub struct List<T> {
-    head: Link<T>,
-    tail: Link<T>
+use core::fmt;
+use core::ptr::{self, NonNull};
+use crate::allocator::{self, Allocator, Global};
+
+pub struct List<T, A: Allocator = Global> {
+    pub(crate) head: Link<T>,
+    pub(crate) tail: Link<T>,
+    length: usize,
+    allocator: A
 }
 
-type Link<T> = *mut Node<T>;
+pub(crate) type Link<T> = *mut Node<T>;
 
-struct Node<T> {
-    element: T,
-    next: Link<T>
+pub(crate) struct Node<T> {
+    pub(crate) element: T,
+    pub(crate) next: Link<T>
 }
 
+// The list uniquely owns its nodes, so it can cross threads whenever a `Box<T>` could
+unsafe impl<T: Send, A: Allocator + Send> Send for List<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for List<T, A> {}
+
 impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: ptr::null_mut(), tail: ptr::null_mut() }
+        List::new_in(Global)
+    }
+
+    /// Moves every element of `other` onto the back of `self`, leaving `other` empty.
+    /// Only the end pointers are relinked, so this is O(1).
+    ///
+    /// This is only offered for the global allocator: with any other, `other`'s nodes might
+    /// belong to a different arena than the one `self` will eventually free them to.
+    pub fn append(&mut self, other: &mut List<T>) {
+        if other.head.is_null() {
+            return;
+        }
+
+        if !self.tail.is_null() {
+            unsafe { (*self.tail).next = other.head; }
+        } else {
+            self.head = other.head;
+        }
+
+        self.tail = other.tail;
+        self.length += other.length;
+
+        other.head = ptr::null_mut();
+        other.tail = ptr::null_mut();
+        other.length = 0;
+    }
+}
+
+impl<T, A: Allocator> List<T, A> {
+    /// Creates a list whose nodes are allocated and freed through `allocator`.
+    pub fn new_in(allocator: A) -> Self {
+        List { head: ptr::null_mut(), tail: ptr::null_mut(), length: 0, allocator }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.allocator
     }
 
     pub fn push(&mut self, element: T) {
-        let new_tail = Box::into_raw(Box::new(Node {
+        let new_tail = allocator::allocate_node(&self.allocator, Node {
             element,
             next: ptr::null_mut()
-        }));
+        }).as_ptr();
 
         if !self.tail.is_null() {
             unsafe { (*self.tail).next = new_tail; }
@@ -30,6 +73,21 @@ impl<T> List<T> {
         }
 
         self.tail = new_tail;
+        self.length += 1;
+    }
+
+    pub fn push_front(&mut self, element: T) {
+        let new_head = allocator::allocate_node(&self.allocator, Node {
+            element,
+            next: self.head
+        }).as_ptr();
+
+        if self.tail.is_null() {
+            self.tail = new_head;
+        }
+
+        self.head = new_head;
+        self.length += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -41,18 +99,22 @@ impl<T> List<T> {
                 self.tail = ptr::null_mut();
             }
 
-            let boxed_old_head = unsafe {
-                Box::from_raw(old_head)
+            let element = unsafe {
+                let element = ptr::addr_of!((*old_head).element).read();
+                allocator::deallocate_node(&self.allocator, NonNull::new_unchecked(old_head));
+                element
             };
+            self.length -= 1;
 
-            Some(boxed_old_head.element)
+            Some(element)
 
         } else {
             None
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter(self) -> IntoIter<T, A> {
         IntoIter(self)
     }
 
@@ -77,17 +139,99 @@ impl<T> List<T> {
         let node_option: Option<&mut Node<T>> = unsafe { self.head.as_mut() };
         node_option.map(|node_ref| &mut node_ref.element)
     }
+
+    pub fn back(&self) -> Option<&T> {
+        let node_option: Option<&Node<T>> = unsafe { self.tail.as_ref() };
+        node_option.map(|node_ref| &node_ref.element)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        let node_option: Option<&mut Node<T>> = unsafe { self.tail.as_mut() };
+        node_option.map(|node_ref| &mut node_ref.element)
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, A: Allocator + Default> Default for List<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
 }
 
-impl<T> Drop for List<T> {
+impl<T, A: Allocator> Drop for List<T, A> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop() {}
+        self.clear();
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for List<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for List<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = List::new_in(self.allocator.clone());
+        list.extend(self.iter().cloned());
+        list
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for List<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for List<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a List<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut List<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
-pub struct IntoIter<T>(List<T>);
+pub struct IntoIter<T, A: Allocator = Global>(List<T, A>);
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
     type Item = T;
     fn next(&mut self) -> Option<T> {
         self.0.pop()
@@ -126,8 +270,34 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, A: Allocator> serde::Serialize for List<T, A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, A: Allocator + Default> serde::Deserialize<'de> for List<T, A> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_support::deserialize_seq(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, A: Allocator + Default> crate::serde_support::FromSeq<'de> for List<T, A> {
+    fn from_seq<S: serde::de::SeqAccess<'de>>(mut seq: S) -> Result<Self, S::Error> {
+        let mut list = List::new_in(A::default());
+        while let Some(element) = seq.next_element()? {
+            list.push(element);
+        }
+        Ok(list)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use crate::allocator::CountingAllocator;
     use super::List;
     #[test]
     fn push() {
@@ -251,6 +421,118 @@ mod test {
         assert_eq!(list.peek(), Some(&10));
     }
 
+    #[test]
+    fn back() {
+        let mut list = List::new();
+        assert_eq!(list.back(), None);
+        assert_eq!(list.back_mut(), None);
+
+        list.push(10);
+        list.push(12);
+        assert_eq!(list.back(), Some(&12));
+
+        *list.back_mut().unwrap() = 14;
+        assert_eq!(list.back(), Some(&14));
+
+        list.pop();
+        list.pop();
+        assert_eq!(list.back(), None);
+    }
+
+    #[test]
+    fn push_front() {
+        let mut list = List::new();
+        list.push_front(2);
+        list.push(3);
+        list.push_front(1);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), None);
+
+        // The tail pointer must be set when pushing onto an empty list
+        list.push_front(4);
+        list.push(5);
+        assert_eq!(list.back(), Some(&5));
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(5));
+    }
+
+    #[test]
+    fn len() {
+        let mut list = List::new();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.len(), 2);
+        assert!(!list.is_empty());
+
+        list.pop();
+        assert_eq!(list.len(), 1);
+
+        list.clear();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+        assert_eq!(list.peek(), None);
+        assert_eq!(list.back(), None);
+
+        list.push(3);
+        assert_eq!(list.pop(), Some(3));
+    }
+
+    #[test]
+    fn append() {
+        let mut list: List<i32> = (1..=3).collect();
+        let mut other: List<i32> = (4..=6).collect();
+
+        list.append(&mut other);
+        assert_eq!(list.len(), 6);
+        assert!(other.is_empty());
+        assert_eq!(other.pop(), None);
+        assert_eq!(list.back(), Some(&6));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+
+        // Appending onto an empty list takes over the other list's ends
+        let mut empty = List::new();
+        empty.append(&mut list);
+        assert_eq!(empty.len(), 6);
+        assert_eq!(empty.peek(), Some(&1));
+        empty.push(7);
+        assert_eq!(empty.back(), Some(&7));
+
+        // Appending an empty list is a no-op
+        empty.append(&mut list);
+        assert_eq!(empty.len(), 7);
+
+        // The drained list is still usable
+        other.push(8);
+        assert_eq!(other.pop(), Some(8));
+    }
+
+    #[test]
+    fn traits() {
+        let mut list: List<i32> = vec![1, 2].into_iter().collect();
+        list.extend(vec![3, 4]);
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4]");
+
+        let mut cloned = list.clone();
+        cloned.push(5);
+        assert_eq!(list.len(), 4);
+        assert_eq!(cloned.len(), 5);
+        assert_eq!(format!("{:?}", cloned), "[1, 2, 3, 4, 5]");
+
+        for element in &mut list {
+            *element *= 10;
+        }
+        assert_eq!((&list).into_iter().sum::<i32>(), 100);
+    }
+
     #[test]
     fn peek_mut() {
         let mut list = List::new();
@@ -266,4 +548,46 @@ mod test {
 
         assert_eq!(list.peek(), Some(&22));
     }
+
+    #[test]
+    fn custom_allocator() {
+        let allocator = CountingAllocator::new();
+        {
+            let mut list = List::new_in(&allocator);
+            list.push(String::from("a"));
+            list.push(String::from("b"));
+            list.push_front(String::from("c"));
+            assert_eq!(allocator.allocations(), 3);
+
+            assert_eq!(list.pop(), Some(String::from("c")));
+            assert_eq!(allocator.deallocations(), 1);
+
+            // Clones allocate from the same allocator
+            let cloned = list.clone();
+            assert_eq!(allocator.live(), 4);
+            drop(cloned);
+            assert_eq!(allocator.live(), 2);
+        }
+        assert_eq!(allocator.allocations(), 5);
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::serde_support::json;
+
+        let list: List<Option<u64>> = [Some(1), None, Some(u64::MAX)].into_iter().collect();
+        let text = json::to_string(&list).unwrap();
+        assert_eq!(text, "[1,null,18446744073709551615]");
+
+        let decoded: List<Option<u64>> = json::from_str(&text).unwrap();
+        assert!(decoded.iter().eq(list.iter()));
+        assert_eq!(decoded.len(), 3);
+
+        // Elements go straight into nodes from the list's own allocator
+        let decoded: List<i32, CountingAllocator> = json::from_str("[4, 5, 6]").unwrap();
+        assert_eq!(decoded.allocator().allocations(), 3);
+        assert!(json::from_str::<List<i32>>("[1, 2, \"three\"]").is_err());
+    }
 }
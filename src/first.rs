@@ -1,9 +1,10 @@
-use std::mem;
+use core::mem;
+use alloc::boxed::Box;
 
 
 #[derive(Debug)]
 pub struct List<T> {
-    head: Link<T>
+    pub(crate) head: Link<T>
 }
 
 impl<T> List<T> {
@@ -30,6 +31,12 @@ impl<T> List<T> {
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur_link = mem::replace(&mut self.head, Link::Empty);
@@ -44,13 +51,13 @@ impl<T> Drop for List<T> {
 }
 
 #[derive(Debug)]
-struct Node<T> {
-    element: T,
-    next: Link<T>
+pub(crate) struct Node<T> {
+    pub(crate) element: T,
+    pub(crate) next: Link<T>
 }
 
 #[derive(Debug)]
-enum Link<T> {
+pub(crate) enum Link<T> {
     More(Box<Node<T>>),
     Empty
 }
@@ -1,20 +1,24 @@
-use std::rc::Rc;
-use std::cell::{Ref, RefCell, RefMut};
-use std::fmt::Debug;
+use alloc::rc::Rc;
+use core::cell::{Ref, RefCell, RefMut};
+#[cfg(any(test, feature = "debug-invariants"))]
+use alloc::collections::BTreeSet;
+use core::fmt::Debug;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::InvariantError;
 
 #[derive(Debug)]
 pub struct List<T: Debug> {
-    head: Link<T>,
-    tail: Link<T>
+    pub(crate) head: Link<T>,
+    pub(crate) tail: Link<T>
 }
 
-type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+pub(crate) type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
 #[derive(Debug)]
 pub struct Node<T> {
-    previous: Link<T>,
-    next: Link<T>,
-    element: T
+    pub(crate) previous: Link<T>,
+    pub(crate) next: Link<T>,
+    pub(crate) element: T
 }
 
 impl<T: Debug> List<T> {
@@ -30,9 +34,9 @@ impl<T: Debug> List<T> {
             previous: None,
             element
         })));
-        prev_head.map(|rc_node| {
+        if let Some(rc_node) = prev_head {
             rc_node.borrow_mut().previous = self.head.clone();
-        });
+        }
         if self.tail.is_none() {
             self.tail = self.head.clone();
         }
@@ -45,9 +49,9 @@ impl<T: Debug> List<T> {
             previous: prev_tail.clone(),
             element
         })));
-        prev_tail.map(|rc_node| {
+        if let Some(rc_node) = prev_tail {
             rc_node.borrow_mut().next = self.tail.clone();
-        });
+        }
         if self.head.is_none() {
             self.head = self.tail.clone();
         }
@@ -77,35 +81,88 @@ impl<T: Debug> List<T> {
         })
     }
 
-    pub fn peek_head(&self) -> Option<Ref<T>> {
+    pub fn peek_head(&self) -> Option<Ref<'_, T>> {
         self.head.as_ref().map(|rc_node| {
             Ref::map(rc_node.borrow(), |node| &node.element)
         })
     }
 
-    pub fn peek_tail(&self) -> Option<Ref<T>> {
+    pub fn peek_tail(&self) -> Option<Ref<'_, T>> {
         self.tail.as_ref().map(|rc_node| {
             Ref::map(rc_node.borrow(), |node| &node.element)
         })
     }
 
-    pub fn peek_head_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_head_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.head.as_mut().map(|rc_node| {
             RefMut::map(rc_node.borrow_mut(), |node| &mut node.element)
         })
     }
 
-    pub fn peek_tail_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_tail_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.tail.as_mut().map(|rc_node| {
             RefMut::map(rc_node.borrow_mut(), |node| &mut node.element)
         })
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
 
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Debug> List<T> {
+    /// Walks the list forward and backward and checks that its links agree.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        if self.head.is_none() != self.tail.is_none() {
+            return Err(InvariantError::MismatchedEnds);
+        }
+        if self.head.as_ref().is_some_and(|head| head.borrow().previous.is_some()) {
+            return Err(InvariantError::HeadHasPrevious);
+        }
+        if self.tail.as_ref().is_some_and(|tail| tail.borrow().next.is_some()) {
+            return Err(InvariantError::TailHasNext);
+        }
+
+        // Walk forward along the `next` links until they run out or loop back
+        let address = |link: &Link<T>| link.as_ref().map(Rc::as_ptr);
+        let mut visited = BTreeSet::new();
+        let mut last = None;
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            if !visited.insert(Rc::as_ptr(&node)) {
+                return Err(InvariantError::Cycle { index: visited.len() - 1 });
+            }
+            current = node.borrow().next.clone();
+            last = Some(node);
+        }
+        if address(&last) != address(&self.tail) {
+            return Err(InvariantError::TailUnreachable);
+        }
+
+        // Walk back along the `previous` links, checking each one leads to a node whose `next` is us
+        let mut current = self.tail.clone();
+        for index in (1..visited.len()).rev() {
+            let previous = current.as_ref().unwrap().borrow().previous.clone();
+            if previous.as_ref().is_none_or(|previous| address(&previous.borrow().next) != address(&current)) {
+                return Err(InvariantError::BrokenBackLink { index });
+            }
+            current = previous;
+        }
+        if address(&current) != address(&self.head) {
+            return Err(InvariantError::HeadUnreachable);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Debug> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Debug> Drop for List<T> {
     fn drop(&mut self) {
         while self.pop_head().is_some() {}
@@ -114,6 +171,14 @@ impl<T: Debug> Drop for List<T> {
 
 pub struct IntoIter<T: Debug>(List<T>);
 
+impl<T: Debug> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
 impl<T: Debug> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
@@ -130,6 +195,8 @@ impl<T: Debug> DoubleEndedIterator for IntoIter<T> {
 #[cfg(test)]
 mod test {
     use std::ops::Deref;
+    use std::rc::Rc;
+    use crate::invariants::InvariantError;
     use super::List;
 
     #[test]
@@ -138,27 +205,39 @@ mod test {
 
         // Check empty list behaves right
         assert_eq!(list.pop_head(), None);
+        list.check_invariants().unwrap();
 
         // Populate list
         list.push_head(1);
+        list.check_invariants().unwrap();
         list.push_head(2);
+        list.check_invariants().unwrap();
         list.push_head(3);
+        list.check_invariants().unwrap();
 
         // Check normal removal
         assert_eq!(list.pop_head(), Some(3));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), Some(2));
+        list.check_invariants().unwrap();
 
         // Push some more just to make sure nothing's corrupted
         list.push_head(4);
+        list.check_invariants().unwrap();
         list.push_head(5);
+        list.check_invariants().unwrap();
 
         // Check normal removal
         assert_eq!(list.pop_head(), Some(5));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), Some(4));
+        list.check_invariants().unwrap();
 
         // Check exhaustion
         assert_eq!(list.pop_head(), Some(1));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), None);
+        list.check_invariants().unwrap();
     }
 
     #[test]
@@ -166,15 +245,24 @@ mod test {
         let mut list = List::new();
 
         list.push_tail(10);
+        list.check_invariants().unwrap();
         list.push_tail(20);
+        list.check_invariants().unwrap();
         list.push_tail(15);
+        list.check_invariants().unwrap();
         list.push_tail(12);
+        list.check_invariants().unwrap();
 
         assert_eq!(list.pop_head(), Some(10));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), Some(20));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), Some(15));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), Some(12));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_head(), None);
+        list.check_invariants().unwrap();
     }
 
     #[test]
@@ -182,25 +270,38 @@ mod test {
         let mut list = List::new();
 
         list.push_head(10);
+        list.check_invariants().unwrap();
         list.push_head(20);
+        list.check_invariants().unwrap();
         list.push_head(15);
+        list.check_invariants().unwrap();
         list.push_head(12);
+        list.check_invariants().unwrap();
 
         assert_eq!(list.pop_tail(), Some(10));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_tail(), Some(20));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_tail(), Some(15));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_tail(), Some(12));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_tail(), None);
+        list.check_invariants().unwrap();
     }
 
     #[test]
     fn peek_mut() {
         let mut list = List::new();
         list.push_head(23);
+        list.check_invariants().unwrap();
         list.push_head(12);
+        list.check_invariants().unwrap();
         list.push_head(92);
+        list.check_invariants().unwrap();
         let head = list.peek_head_mut();
         *head.unwrap() = 10;
+        list.check_invariants().unwrap();
 
         assert_eq!(list.peek_head().unwrap().deref(), &10);
     }
@@ -212,12 +313,15 @@ mod test {
 
         // The tutorial said to use `&*` instead of `.deref()`. I think `.deref()` looks nicer.
         list.push_head(5);
+        list.check_invariants().unwrap();
         assert_eq!(list.peek_head().unwrap().deref(), &5);
 
         list.push_head(10);
+        list.check_invariants().unwrap();
         assert_eq!(list.peek_head().unwrap().deref(), &10);
 
         list.pop_head();
+        list.check_invariants().unwrap();
         assert_eq!(list.peek_head().unwrap().deref(), &5);
     }
 
@@ -225,6 +329,7 @@ mod test {
     fn into_iter() {
         let mut list = List::new();
         list.push_head(1); list.push_head(2); list.push_head(3);
+        list.check_invariants().unwrap();
 
         let mut iter = list.into_iter();
         assert_eq!(iter.next(), Some(3));
@@ -234,4 +339,44 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn broken_invariants() {
+        let mut list = List::new();
+        for i in 0..3 {
+            list.push_tail(i);
+            list.check_invariants().unwrap();
+        }
+        let a = list.head.clone().unwrap();
+        let b = a.borrow().next.clone().unwrap();
+        let c = list.tail.clone().unwrap();
+
+        b.borrow_mut().previous = None;
+        assert_eq!(list.check_invariants(), Err(InvariantError::BrokenBackLink { index: 1 }));
+        b.borrow_mut().previous = Some(c.clone());
+        assert_eq!(list.check_invariants(), Err(InvariantError::BrokenBackLink { index: 1 }));
+        b.borrow_mut().previous = Some(a.clone());
+
+        b.borrow_mut().next = Some(a.clone());
+        assert_eq!(list.check_invariants(), Err(InvariantError::Cycle { index: 1 }));
+        b.borrow_mut().next = Some(c.clone());
+
+        a.borrow_mut().next = Some(c.clone());
+        assert_eq!(list.check_invariants(), Err(InvariantError::HeadUnreachable));
+        a.borrow_mut().next = Some(b.clone());
+
+        list.tail = Some(b.clone());
+        assert_eq!(list.check_invariants(), Err(InvariantError::TailHasNext));
+        list.tail = Some(c.clone());
+
+        a.borrow_mut().previous = Some(c.clone());
+        assert_eq!(list.check_invariants(), Err(InvariantError::HeadHasPrevious));
+        a.borrow_mut().previous = None;
+
+        list.tail = None;
+        assert_eq!(list.check_invariants(), Err(InvariantError::MismatchedEnds));
+        list.tail = Some(c);
+        list.check_invariants().unwrap();
+        // Held by the list's head, `b`'s previous link and `a` itself
+        assert_eq!(Rc::strong_count(&a), 3);
+    }
 }
@@ -0,0 +1,411 @@
+//! A doubly linked list threaded through its elements, for objects that already live somewhere
+//! and can't pay for a separate node allocation per list they join.
+//!
+//! Element types embed a `Links` field and implement `Linked`, usually through the
+//! `intrusive_adapter!` macro. The list borrows each element as a `Pin<&'a T>` for as long as
+//! the list itself lives, so elements can't move or be dropped while they're linked. Links sit
+//! in `Cell`s, which is what lets the list relink elements it only holds shared references to.
+//!
+//! Each list has a unique id that linked elements record, so `remove` can tell in O(1) whether
+//! an element belongs to it. Dropping a list unlinks everything, letting the elements join
+//! another one.
+
+use core::cell::Cell;
+use core::fmt;
+use core::marker::{PhantomData, PhantomPinned};
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+type Link<T> = Option<NonNull<T>>;
+
+/// The `previous`/`next` pointers an element embeds to be part of an `IntrusiveList`.
+pub struct Links<T> {
+    previous: Cell<Link<T>>,
+    next: Cell<Link<T>>,
+    // The id of the list the element is in, or `UNLINKED`
+    owner: Cell<usize>,
+    _pin: PhantomPinned
+}
+
+const UNLINKED: usize = 0;
+
+impl<T> Links<T> {
+    pub const fn new() -> Self {
+        Links { previous: Cell::new(None), next: Cell::new(None), owner: Cell::new(UNLINKED), _pin: PhantomPinned }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.owner.get() != UNLINKED
+    }
+}
+
+impl<T> Default for Links<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Links<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Links").field("linked", &self.is_linked()).finish_non_exhaustive()
+    }
+}
+
+/// A type with an embedded `Links` field.
+///
+/// # Safety
+/// `links` must return the same field of `self` every time, and no other `Links` field may be
+/// handed to the same list.
+pub unsafe trait Linked: Sized {
+    fn links(&self) -> &Links<Self>;
+}
+
+/// Implements `Linked` for a struct through one of its `Links` fields.
+///
+/// ```
+/// use linked_list::intrusive::Links;
+///
+/// struct Task {
+///     id: u32,
+///     queue: Links<Task>
+/// }
+///
+/// linked_list::intrusive_adapter!(Task, queue);
+/// ```
+#[macro_export]
+macro_rules! intrusive_adapter {
+    ($type:ty, $field:ident) => {
+        unsafe impl $crate::intrusive::Linked for $type {
+            fn links(&self) -> &$crate::intrusive::Links<Self> {
+                &self.$field
+            }
+        }
+    };
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(UNLINKED + 1);
+
+// Ids are never reused: a wrapped counter could hand out `UNLINKED` or a live list's id
+fn take_id(counter: &AtomicUsize) -> usize {
+    counter
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+        .expect("ran out of intrusive list ids")
+}
+
+pub struct IntrusiveList<'a, T: Linked> {
+    head: Link<T>,
+    tail: Link<T>,
+    length: usize,
+    id: usize,
+    _boo: PhantomData<Pin<&'a T>>
+}
+
+impl<'a, T: Linked> IntrusiveList<'a, T> {
+    /// # Panics
+    /// Panics once `usize::MAX` lists have been created, rather than reuse an id.
+    pub fn new() -> Self {
+        let id = take_id(&NEXT_ID);
+        IntrusiveList { head: None, tail: None, length: 0, id, _boo: PhantomData }
+    }
+
+    /// # Panics
+    /// Panics if `element` is already in a list, this one or any other.
+    pub fn push_front(&mut self, element: Pin<&'a T>) {
+        let node = self.claim(element);
+        let links = element.get_ref().links();
+        links.next.set(self.head);
+        match self.head {
+            Some(head) => unsafe { Self::links_of(head).previous.set(Some(node)) },
+            None => self.tail = Some(node)
+        }
+        self.head = Some(node);
+        self.length += 1;
+    }
+
+    /// # Panics
+    /// Panics if `element` is already in a list, this one or any other.
+    pub fn push_back(&mut self, element: Pin<&'a T>) {
+        let node = self.claim(element);
+        let links = element.get_ref().links();
+        links.previous.set(self.tail);
+        match self.tail {
+            Some(tail) => unsafe { Self::links_of(tail).next.set(Some(node)) },
+            None => self.head = Some(node)
+        }
+        self.tail = Some(node);
+        self.length += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<Pin<&'a T>> {
+        let head = self.head?;
+        let element = unsafe { Pin::new_unchecked(&*head.as_ptr()) };
+        self.unlink(element.get_ref());
+        Some(element)
+    }
+
+    pub fn pop_back(&mut self) -> Option<Pin<&'a T>> {
+        let tail = self.tail?;
+        let element = unsafe { Pin::new_unchecked(&*tail.as_ptr()) };
+        self.unlink(element.get_ref());
+        Some(element)
+    }
+
+    /// Unlinks `element` in O(1), returning whether it was in this list.
+    pub fn remove(&mut self, element: Pin<&T>) -> bool {
+        if !self.contains(element) {
+            return false;
+        }
+        self.unlink(element.get_ref());
+        true
+    }
+
+    /// Whether `element` is in this list, in O(1).
+    pub fn contains(&self, element: Pin<&T>) -> bool {
+        element.get_ref().links().owner.get() == self.id
+    }
+
+    pub fn front(&self) -> Option<Pin<&'a T>> {
+        self.head.map(|head| unsafe { Pin::new_unchecked(&*head.as_ptr()) })
+    }
+
+    pub fn back(&self) -> Option<Pin<&'a T>> {
+        self.tail.map(|tail| unsafe { Pin::new_unchecked(&*tail.as_ptr()) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Unlinks every element, leaving them free to join another list.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { head: self.head, tail: self.tail, length: self.length, _boo: PhantomData }
+    }
+
+    // Marks `element` as ours, refusing it if it's linked anywhere already
+    fn claim(&self, element: Pin<&'a T>) -> NonNull<T> {
+        let links = element.get_ref().links();
+        assert!(!links.is_linked(), "element is already in an intrusive list");
+        links.owner.set(self.id);
+        NonNull::from(element.get_ref())
+    }
+
+    // Unlinks an element known to be in this list
+    fn unlink(&mut self, element: &T) {
+        let links = element.links();
+        let (previous, next) = (links.previous.take(), links.next.take());
+        match previous {
+            Some(previous) => unsafe { Self::links_of(previous).next.set(next) },
+            None => self.head = next
+        }
+        match next {
+            Some(next) => unsafe { Self::links_of(next).previous.set(previous) },
+            None => self.tail = previous
+        }
+        links.owner.set(UNLINKED);
+        self.length -= 1;
+    }
+
+    // Every node in the list came from a `Pin<&'a T>` that is still borrowed
+    unsafe fn links_of<'b>(node: NonNull<T>) -> &'b Links<T> {
+        (*node.as_ptr()).links()
+    }
+}
+
+impl<T: Linked> Default for IntrusiveList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Linked> Drop for IntrusiveList<'_, T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Linked + fmt::Debug> fmt::Debug for IntrusiveList<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    length: usize,
+    _boo: PhantomData<&'a T>
+}
+
+impl<'a, T: Linked> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.length == 0 {
+            return None;
+        }
+        self.head.map(|head| unsafe {
+            let element = &*head.as_ptr();
+            self.head = element.links().next.get();
+            self.length -= 1;
+            element
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<T: Linked> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.length == 0 {
+            return None;
+        }
+        self.tail.map(|tail| unsafe {
+            let element = &*tail.as_ptr();
+            self.tail = element.links().previous.get();
+            self.length -= 1;
+            element
+        })
+    }
+}
+
+impl<T: Linked> ExactSizeIterator for Iter<'_, T> {
+    fn len(&self) -> usize {
+        self.length
+    }
+}
+
+impl<'a, T: Linked> IntoIterator for &'a IntrusiveList<'_, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::pin::pin;
+    use std::sync::atomic::AtomicUsize;
+    use super::{take_id, IntrusiveList, Links};
+
+    #[derive(Debug)]
+    struct Task {
+        id: u32,
+        queue: Links<Task>
+    }
+
+    crate::intrusive_adapter!(Task, queue);
+
+    fn task(id: u32) -> Task {
+        Task { id, queue: Links::new() }
+    }
+
+    fn ids(list: &IntrusiveList<'_, Task>) -> Vec<u32> {
+        list.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn basics() {
+        let (a, b, c) = (pin!(task(1)), pin!(task(2)), pin!(task(3)));
+        let (a, b, c) = (a.into_ref(), b.into_ref(), c.into_ref());
+        let mut list = IntrusiveList::new();
+
+        // Check empty list behaves right
+        assert!(list.is_empty());
+        assert!(list.pop_front().is_none());
+
+        list.push_back(b);
+        list.push_front(a);
+        list.push_back(c);
+        assert_eq!(ids(&list), vec![1, 2, 3]);
+        assert_eq!(list.iter().rev().map(|task| task.id).collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(list.len(), 3);
+        assert!(b.queue.is_linked());
+
+        assert_eq!(list.pop_front().unwrap().id, 1);
+        assert_eq!(list.pop_back().unwrap().id, 3);
+        assert!(!a.queue.is_linked());
+        assert_eq!((list.front().unwrap().id, list.back().unwrap().id), (2, 2));
+        assert_eq!(list.pop_back().unwrap().id, 2);
+        assert!(list.pop_back().is_none());
+    }
+
+    #[test]
+    fn remove() {
+        let boxed: Vec<_> = (0..4).map(|id| Box::pin(task(id))).collect();
+        let tasks: Vec<_> = boxed.iter().map(|task| task.as_ref()).collect();
+        let mut list = IntrusiveList::new();
+        for &task in &tasks {
+            list.push_back(task);
+        }
+
+        // Middle, then both ends
+        assert!(list.remove(tasks[2]));
+        assert_eq!(ids(&list), vec![0, 1, 3]);
+        assert!(list.remove(tasks[0]));
+        assert!(list.remove(tasks[3]));
+        assert_eq!(ids(&list), vec![1]);
+        assert_eq!(list.iter().rev().map(|task| task.id).collect::<Vec<_>>(), vec![1]);
+
+        // Removing twice, or from a list it isn't in, does nothing
+        assert!(!list.remove(tasks[2]));
+        let mut other = IntrusiveList::new();
+        other.push_back(tasks[2]);
+        assert!(!list.remove(tasks[2]));
+        assert!(!other.remove(tasks[1]));
+        assert!(other.contains(tasks[2]) && !list.contains(tasks[2]));
+        assert_eq!((list.len(), other.len()), (1, 1));
+
+        // A removed element can be pushed again
+        list.push_front(tasks[0]);
+        assert_eq!(ids(&list), vec![0, 1]);
+    }
+
+    #[test]
+    fn dropping_a_list_unlinks_its_elements() {
+        let (a, b) = (pin!(task(1)), pin!(task(2)));
+        let (a, b) = (a.into_ref(), b.into_ref());
+        {
+            let mut list = IntrusiveList::new();
+            list.push_back(a);
+            list.push_back(b);
+        }
+        assert!(!a.queue.is_linked() && !b.queue.is_linked());
+
+        let mut list = IntrusiveList::new();
+        list.push_back(b);
+        list.push_back(a);
+        assert_eq!(ids(&list), vec![2, 1]);
+        assert_eq!(format!("{:?}", list.front().unwrap().queue), "Links { linked: true, .. }");
+    }
+
+    #[test]
+    #[should_panic(expected = "already in an intrusive list")]
+    fn pushing_twice_panics() {
+        let a = pin!(task(1));
+        let a = a.into_ref();
+        let mut list = IntrusiveList::new();
+        list.push_back(a);
+        list.push_front(a);
+    }
+
+    #[test]
+    #[should_panic(expected = "ran out of intrusive list ids")]
+    fn ids_are_never_reused() {
+        let counter = AtomicUsize::new(usize::MAX - 1);
+        assert_eq!(take_id(&counter), usize::MAX - 1);
+        take_id(&counter);
+    }
+}
@@ -0,0 +1,47 @@
+//! The error type for the doubly linked lists' `check_invariants`, which is built for tests
+//! and with the `debug-invariants` feature.
+
+use core::error::Error;
+use core::fmt;
+
+/// The first broken structural rule `check_invariants` found. Node indices count from the head.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantError {
+    /// Exactly one of `head` and `tail` is set.
+    MismatchedEnds,
+    /// The head node has a `previous` link.
+    HeadHasPrevious,
+    /// The tail node has a `next` link.
+    TailHasNext,
+    /// The `next` link of the node at `index` loops back to an earlier node.
+    Cycle { index: usize },
+    /// Walking forward from the head ends somewhere other than `tail`.
+    TailUnreachable,
+    /// The `previous` link of the node at `index` doesn't lead to a node whose `next` is it.
+    BrokenBackLink { index: usize },
+    /// Walking back from the tail ends somewhere other than `head`.
+    HeadUnreachable,
+    /// The list's `length` doesn't match the number of nodes actually linked.
+    LengthMismatch { length: usize, counted: usize }
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::MismatchedEnds => f.write_str("only one of head and tail is set"),
+            InvariantError::HeadHasPrevious => f.write_str("the head node has a previous link"),
+            InvariantError::TailHasNext => f.write_str("the tail node has a next link"),
+            InvariantError::Cycle { index } => write!(f, "node {} links forward to an earlier node", index),
+            InvariantError::TailUnreachable => f.write_str("walking forward from the head doesn't end at the tail"),
+            InvariantError::BrokenBackLink { index } => {
+                write!(f, "node {} doesn't link back to the node before it", index)
+            }
+            InvariantError::HeadUnreachable => f.write_str("walking back from the tail doesn't end at the head"),
+            InvariantError::LengthMismatch { length, counted } => {
+                write!(f, "length is {} but {} nodes are linked", length, counted)
+            }
+        }
+    }
+}
+
+impl Error for InvariantError {}
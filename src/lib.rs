@@ -1,6 +1,39 @@
+// Without the `std` feature the crate is `no_std` + `alloc`, minus the modules that need threads
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod first;
 pub mod second;
 pub mod third;
 pub mod fourth;
 pub mod fifth;
-pub mod sixth;
\ No newline at end of file
+pub mod sixth;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "std")]
+mod epoch;
+#[cfg(feature = "std")]
+pub mod lockfree_queue;
+#[cfg(feature = "std")]
+pub mod lockfree_stack;
+#[cfg(feature = "std")]
+pub mod channel;
+#[cfg(feature = "async")]
+pub mod async_queue;
+pub mod allocator;
+pub mod arena_list;
+pub mod unrolled;
+pub mod skiplist;
+pub mod sorted;
+pub mod circular;
+pub mod intrusive;
+pub mod bounded_deque;
+pub mod xor;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod snapshot;
+pub mod debug_viz;
+#[cfg(any(test, feature = "debug-invariants"))]
+pub mod invariants;
+pub mod replay;
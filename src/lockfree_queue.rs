@@ -0,0 +1,288 @@
+//! A Michael–Scott lock-free queue.
+//!
+//! The layout is the one `fifth::List` uses (push at the tail, pop at the head) with both
+//! ends made atomic. The head always points at a sentinel node whose element has already
+//! been taken, so pushers and poppers never touch the same node's element.
+
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use crate::epoch;
+
+pub struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    _boo: PhantomData<T>
+}
+
+struct Node<T> {
+    element: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>
+}
+
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        let sentinel = Box::into_raw(Box::new(Node {
+            element: MaybeUninit::uninit(),
+            next: AtomicPtr::new(ptr::null_mut())
+        }));
+        Queue {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            _boo: PhantomData
+        }
+    }
+
+    pub fn push(&self, element: T) {
+        let new_tail = Box::into_raw(Box::new(Node {
+            element: MaybeUninit::new(element),
+            next: AtomicPtr::new(ptr::null_mut())
+        }));
+
+        let _guard = epoch::pin();
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+
+            if next.is_null() {
+                let linked = unsafe {
+                    (*tail).next.compare_exchange(ptr::null_mut(), new_tail, Ordering::Release, Ordering::Relaxed)
+                };
+                if linked.is_ok() {
+                    // If this fails another thread has already swung the tail past us
+                    let _ = self.tail.compare_exchange(tail, new_tail, Ordering::Release, Ordering::Relaxed);
+                    return;
+                }
+            } else {
+                // The tail is lagging behind a half-finished push; help it along
+                let _ = self.tail.compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let next = unsafe { (*head).next.load(Ordering::Acquire) };
+            if next.is_null() {
+                return None;
+            }
+
+            // Never retire a node the tail still points at
+            let tail = self.tail.load(Ordering::Acquire);
+            if head == tail {
+                let _ = self.tail.compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+
+            if self.head.compare_exchange(head, next, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+                unsafe {
+                    // `next` is the new sentinel; winning the CAS gives us sole claim on its element
+                    let element = (*next).element.as_ptr().read();
+                    guard.defer_destroy(head);
+                    return Some(element);
+                }
+            }
+        }
+    }
+
+    /// A snapshot that may be stale by the time it is returned if other threads are active.
+    pub fn is_empty(&self) -> bool {
+        let _guard = epoch::pin();
+        let head = self.head.load(Ordering::Acquire);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        // We have exclusive access, so the remaining nodes can be freed immediately
+        unsafe {
+            let sentinel = Box::from_raw(*self.head.get_mut());
+            let mut current = sentinel.next.load(Ordering::Relaxed);
+            while !current.is_null() {
+                let node = Box::from_raw(current);
+                current = node.next.load(Ordering::Relaxed);
+                drop(node.element.assume_init());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use super::Queue;
+
+    #[test]
+    fn basics() {
+        let queue = Queue::new();
+
+        // Check empty queue behaves right
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+
+        // Populate queue
+        queue.push(1);
+        queue.push(2);
+        queue.push(3);
+        assert!(!queue.is_empty());
+
+        // Check normal removal
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+
+        // Push some more just to make sure nothing's corrupted
+        queue.push(4);
+        queue.push(5);
+
+        // Check normal removal
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+
+        // Check exhaustion
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        // Check the exhaustion case left the sentinel in a usable state
+        queue.push(6);
+        assert_eq!(queue.pop(), Some(6));
+    }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = Queue::new();
+        for _ in 0..10 {
+            queue.push(DropCounter(drops.clone()));
+        }
+        drop(queue.pop());
+        drop(queue.pop());
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+
+        drop(queue);
+        assert_eq!(drops.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn mpmc_fifo_per_producer() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const PER_PRODUCER: usize = 20_000;
+
+        let queue = Arc::new(Queue::new());
+        let barrier = Arc::new(Barrier::new(PRODUCERS + CONSUMERS));
+        let remaining = Arc::new(AtomicUsize::new(PRODUCERS * PER_PRODUCER));
+
+        let producers: Vec<_> = (0..PRODUCERS).map(|producer| {
+            let queue = queue.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for sequence in 0..PER_PRODUCER {
+                    queue.push((producer, sequence));
+                }
+            })
+        }).collect();
+
+        let consumers: Vec<_> = (0..CONSUMERS).map(|_| {
+            let queue = queue.clone();
+            let barrier = barrier.clone();
+            let remaining = remaining.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut seen = Vec::new();
+                while remaining.load(Ordering::SeqCst) > 0 {
+                    if let Some(item) = queue.pop() {
+                        remaining.fetch_sub(1, Ordering::SeqCst);
+                        seen.push(item);
+                    }
+                }
+                seen
+            })
+        }).collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut all = HashSet::new();
+        for consumer in consumers {
+            let seen = consumer.join().unwrap();
+
+            // A linearizable FIFO queue can never hand one consumer a producer's items out of order
+            let mut last = [None; PRODUCERS];
+            for &(producer, sequence) in &seen {
+                assert!(last[producer].is_none_or(|previous| previous < sequence));
+                last[producer] = Some(sequence);
+            }
+            for item in seen {
+                assert!(all.insert(item), "{:?} was popped twice", item);
+            }
+        }
+
+        assert_eq!(all.len(), PRODUCERS * PER_PRODUCER);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn mixed_operations_conserve_elements() {
+        const THREADS: usize = 8;
+        const OPERATIONS: usize = 20_000;
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = Arc::new(Queue::new());
+        let popped = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..THREADS).map(|_| {
+            let queue = queue.clone();
+            let drops = drops.clone();
+            let popped = popped.clone();
+            thread::spawn(move || {
+                for i in 0..OPERATIONS {
+                    queue.push(DropCounter(drops.clone()));
+                    if i % 3 != 0 && queue.pop().is_some() {
+                        popped.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        }).collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // Every popped element has already been dropped exactly once
+        assert_eq!(drops.load(Ordering::SeqCst), popped.load(Ordering::SeqCst));
+
+        let mut left = 0;
+        while queue.pop().is_some() {
+            left += 1;
+        }
+        assert_eq!(popped.load(Ordering::SeqCst) + left, THREADS * OPERATIONS);
+        assert_eq!(drops.load(Ordering::SeqCst), THREADS * OPERATIONS);
+    }
+}
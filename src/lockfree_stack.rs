@@ -0,0 +1,319 @@
+//! A Treiber stack: the concurrent counterpart to `second::List`.
+//!
+//! Popped nodes are retired through the epoch scheme in `epoch`, so a node's address can't be
+//! reused while another thread might still compare against it. That rules out ABA without
+//! needing tagged pointers.
+
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use crate::epoch;
+use crate::second;
+
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    _boo: PhantomData<T>
+}
+
+struct Node<T> {
+    // Moved out by whichever thread pops the node; the node itself is freed later
+    element: ManuallyDrop<T>,
+    // Fixed before the node is published
+    next: *mut Node<T>
+}
+
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack { head: AtomicPtr::new(ptr::null_mut()), _boo: PhantomData }
+    }
+
+    pub fn push(&self, element: T) {
+        let new_head = Box::into_raw(Box::new(Node {
+            element: ManuallyDrop::new(element),
+            next: ptr::null_mut()
+        }));
+
+        loop {
+            let head = self.head.load(Ordering::Relaxed);
+            unsafe { (*new_head).next = head; }
+            if self.head.compare_exchange_weak(head, new_head, Ordering::Release, Ordering::Relaxed).is_ok() {
+                return;
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            if head.is_null() {
+                return None;
+            }
+
+            let next = unsafe { (*head).next };
+            if self.head.compare_exchange_weak(head, next, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                unsafe {
+                    let element = ManuallyDrop::take(&mut (*head).element);
+                    guard.defer_destroy(head);
+                    return Some(element);
+                }
+            }
+        }
+    }
+
+    /// Atomically takes every element currently on the stack. The returned list pops them
+    /// in the same order this stack would have.
+    pub fn pop_all(&self) -> second::List<T> {
+        let guard = epoch::pin();
+        let mut current = self.head.swap(ptr::null_mut(), Ordering::Acquire);
+
+        let mut list = second::List::new();
+        while !current.is_null() {
+            unsafe {
+                list.push(ManuallyDrop::take(&mut (*current).element));
+                let next = (*current).next;
+                // Threads that loaded this node before the swap may still be reading `next`
+                guard.defer_destroy(current);
+                current = next;
+            }
+        }
+        list.reverse();
+        list
+    }
+
+    /// Peeking needs exclusive access: with `&self` another thread could pop and drop
+    /// the element while we were still looking at it.
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        let head = *self.head.get_mut();
+        unsafe { head.as_mut().map(|node| &mut *node.element) }
+    }
+
+    /// Checks the top of the stack once, so a concurrent push or pop can change the answer
+    /// before the caller acts on it.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        let mut current = *self.head.get_mut();
+        while !current.is_null() {
+            unsafe {
+                let mut node = Box::from_raw(current);
+                ManuallyDrop::drop(&mut node.element);
+                current = node.next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use super::Stack;
+
+    #[test]
+    fn basics() {
+        let stack = Stack::new();
+
+        // Check empty stack behaves right
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+
+        // Populate stack
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+
+        // Check normal removal
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        // Push some more just to make sure nothing's corrupted
+        stack.push(4);
+        stack.push(5);
+
+        // Check normal removal
+        assert_eq!(stack.pop(), Some(5));
+        assert_eq!(stack.pop(), Some(4));
+
+        // Check exhaustion
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut stack = Stack::new();
+        assert_eq!(stack.peek_mut(), None);
+        stack.push(1); stack.push(2);
+
+        *stack.peek_mut().unwrap() = 42;
+        assert_eq!(stack.pop(), Some(42));
+        assert_eq!(stack.peek_mut(), Some(&mut 1));
+    }
+
+    #[test]
+    fn pop_all() {
+        let stack = Stack::new();
+        assert_eq!(stack.pop_all().pop(), None);
+
+        stack.push(1); stack.push(2); stack.push(3);
+        let mut list = stack.pop_all();
+        assert!(stack.is_empty());
+
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+
+        // The stack is still usable after being drained
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+    }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn drop_remaining() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = Stack::new();
+        for _ in 0..10 {
+            stack.push(DropCounter(drops.clone()));
+        }
+        drop(stack.pop());
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+        let list = {
+            stack.push(DropCounter(drops.clone()));
+            stack.pop_all()
+        };
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        drop(list);
+        assert_eq!(drops.load(Ordering::SeqCst), 11);
+
+        for _ in 0..5 {
+            stack.push(DropCounter(drops.clone()));
+        }
+        drop(stack);
+        assert_eq!(drops.load(Ordering::SeqCst), 16);
+    }
+
+    #[test]
+    fn concurrent_push_pop() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 20_000;
+
+        let stack = Arc::new(Stack::new());
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let threads: Vec<_> = (0..THREADS).map(|thread| {
+            let stack = stack.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                let mut popped = Vec::new();
+                for i in 0..PER_THREAD {
+                    stack.push((thread, i));
+                    if i % 2 == 0 {
+                        popped.extend(stack.pop());
+                    }
+                }
+                popped
+            })
+        }).collect();
+
+        let mut all = HashSet::new();
+        for thread in threads {
+            for item in thread.join().unwrap() {
+                assert!(all.insert(item), "{:?} was popped twice", item);
+            }
+        }
+        while let Some(item) = stack.pop() {
+            assert!(all.insert(item), "{:?} was popped twice", item);
+        }
+        assert_eq!(all.len(), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn concurrent_pop_all() {
+        const PUSHERS: usize = 4;
+        const DRAINERS: usize = 4;
+        const PER_PUSHER: usize = 20_000;
+
+        let stack = Arc::new(Stack::new());
+        let remaining = Arc::new(AtomicUsize::new(PUSHERS * PER_PUSHER));
+
+        let pushers: Vec<_> = (0..PUSHERS).map(|pusher| {
+            let stack = stack.clone();
+            thread::spawn(move || {
+                for i in 0..PER_PUSHER {
+                    stack.push((pusher, i));
+                }
+            })
+        }).collect();
+
+        let drainers: Vec<_> = (0..DRAINERS).map(|drainer| {
+            let stack = stack.clone();
+            let remaining = remaining.clone();
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                while remaining.load(Ordering::SeqCst) > 0 {
+                    // Mix single pops in with bulk takes so they race against each other
+                    let batch: Vec<_> = if drainer % 2 == 0 {
+                        stack.pop_all().into_iter().collect()
+                    } else {
+                        stack.pop().into_iter().collect()
+                    };
+                    if batch.is_empty() {
+                        thread::yield_now();
+                        continue;
+                    }
+                    remaining.fetch_sub(batch.len(), Ordering::SeqCst);
+
+                    // Within one pusher's items, a batch must come out newest first
+                    for window in batch.windows(2) {
+                        if window[0].0 == window[1].0 {
+                            assert!(window[0].1 > window[1].1);
+                        }
+                    }
+                    seen.extend(batch);
+                }
+                seen
+            })
+        }).collect();
+
+        for pusher in pushers {
+            pusher.join().unwrap();
+        }
+
+        let mut all = HashSet::new();
+        for drainer in drainers {
+            for item in drainer.join().unwrap() {
+                assert!(all.insert(item), "{:?} was popped twice", item);
+            }
+        }
+        assert_eq!(all.len(), PUSHERS * PER_PUSHER);
+    }
+}
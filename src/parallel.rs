@@ -0,0 +1,266 @@
+//! Parallel iteration over `sixth::LinkedList` on scoped threads.
+//!
+//! The list is cut into one contiguous chunk per thread. Chunk boundaries are found from the
+//! cached `length` by walking in from whichever end is nearer, so locating them costs at most
+//! half a traversal. Chunk results are combined in list order: `map_collect` always matches a
+//! sequential `map`, and `fold` matches a sequential fold whenever `reduce` is associative.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::num::NonZeroUsize;
+use std::panic;
+use std::thread;
+use crate::allocator::Allocator;
+use crate::sixth::{Iter, Link, LinkedList, Node};
+
+impl<T: Sync, A: Allocator> LinkedList<T, A> {
+    /// Splits the list across as many threads as the machine offers; see `ParIter::threads`.
+    pub fn par_iter(&self) -> ParIter<'_, T> {
+        ParIter {
+            head: self.head_node(),
+            tail: self.tail_node(),
+            length: self.len(),
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            _boo: PhantomData
+        }
+    }
+
+    pub fn par_for_each(&self, f: impl Fn(&T) + Sync) {
+        self.par_iter().for_each(f)
+    }
+
+    pub fn par_map_collect<U: Send>(&self, f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+        self.par_iter().map_collect(f)
+    }
+
+    pub fn par_fold<U: Send>(
+        &self,
+        identity: impl Fn() -> U + Sync,
+        fold: impl Fn(U, &T) -> U + Sync,
+        reduce: impl FnMut(U, U) -> U
+    ) -> U {
+        self.par_iter().fold(identity, fold, reduce)
+    }
+}
+
+/// A borrowed list about to be processed in parallel chunks.
+pub struct ParIter<'a, T> {
+    head: Link<T>,
+    tail: Link<T>,
+    length: usize,
+    threads: usize,
+    _boo: PhantomData<&'a T>
+}
+
+impl<'a, T: Sync> ParIter<'a, T> {
+    /// Uses at most `threads` threads, counting the calling one. Zero is treated as one.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Calls `f` on every element, in list order within each chunk.
+    pub fn for_each(self, f: impl Fn(&T) + Sync) {
+        self.run(|chunk| chunk.for_each(&f));
+    }
+
+    /// Maps every element through `f`, collecting the results in list order.
+    pub fn map_collect<U: Send>(self, f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+        let mut collected = Vec::with_capacity(self.length);
+        for chunk in self.run(|chunk| chunk.map(&f).collect::<Vec<_>>()) {
+            collected.extend(chunk);
+        }
+        collected
+    }
+
+    /// Folds each chunk from a fresh `identity()`, then combines the chunk results front to
+    /// back with `reduce`. An empty list folds to `identity()`.
+    pub fn fold<U: Send>(
+        self,
+        identity: impl Fn() -> U + Sync,
+        fold: impl Fn(U, &T) -> U + Sync,
+        reduce: impl FnMut(U, U) -> U
+    ) -> U {
+        self.run(|chunk| chunk.fold(identity(), &fold))
+            .into_iter()
+            .reduce(reduce)
+            .unwrap_or_else(identity)
+    }
+
+    /// Splits the list into at most `threads` chunks whose lengths differ by at most one.
+    fn chunks(&self) -> Vec<Iter<'a, T>> {
+        let count = self.threads.min(self.length);
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // The first and last position of every chunk, ascending
+        let (base, extra) = (self.length / count, self.length % count);
+        let mut bounds = Vec::with_capacity(count * 2);
+        let mut start = 0;
+        for i in 0..count {
+            let length = base + usize::from(i < extra);
+            bounds.extend([start, start + length - 1]);
+            start += length;
+        }
+
+        // Reach the front half from the head and the back half from the tail
+        let middle = self.length / 2;
+        let mut nodes = Vec::with_capacity(bounds.len());
+        let (mut position, mut node) = (0, self.head);
+        for &bound in bounds.iter().take_while(|&&bound| bound < middle) {
+            while position < bound {
+                node = unsafe { Node::next(node.unwrap()) };
+                position += 1;
+            }
+            nodes.push(node);
+        }
+        let mut back = Vec::with_capacity(bounds.len() - nodes.len());
+        let (mut position, mut node) = (self.length - 1, self.tail);
+        for &bound in bounds.iter().rev().take_while(|&&bound| bound >= middle) {
+            while position > bound {
+                node = unsafe { Node::previous(node.unwrap()) };
+                position -= 1;
+            }
+            back.push(node);
+        }
+        nodes.extend(back.into_iter().rev());
+
+        bounds
+            .chunks(2)
+            .zip(nodes.chunks(2))
+            .map(|(bound, node)| unsafe { Iter::new(node[0], node[1], bound[1] - bound[0] + 1) })
+            .collect()
+    }
+
+    // Runs `work` on every chunk, the first on the calling thread, and returns the results in order
+    fn run<R: Send>(&self, work: impl Fn(Iter<'a, T>) -> R + Sync) -> Vec<R> {
+        let mut chunks = self.chunks().into_iter();
+        let Some(first) = chunks.next() else {
+            return Vec::new();
+        };
+        thread::scope(|scope| {
+            let work = &work;
+            let handles: Vec<_> = chunks.map(|chunk| scope.spawn(move || work(chunk))).collect();
+            let mut results = Vec::with_capacity(handles.len() + 1);
+            results.push(work(first));
+            for handle in handles {
+                results.push(handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload)));
+            }
+            results
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+    use crate::sixth::LinkedList;
+
+    fn list_of(length: u32) -> LinkedList<u32> {
+        // xorshift32, so the elements aren't in any order a broken split could get right by luck
+        let mut state = 0x2545_f491_u32;
+        let mut list = LinkedList::new();
+        for _ in 0..length {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            list.push_back(state % 1000);
+        }
+        list
+    }
+
+    #[test]
+    fn chunks_cover_every_node_once() {
+        for length in 0..24 {
+            let list = list_of(length);
+            let expected: Vec<_> = list.iter().collect();
+            for threads in 1..8 {
+                let chunks = list.par_iter().threads(threads).chunks();
+                assert_eq!(chunks.len(), (threads as u32).min(length) as usize);
+                let lengths: Vec<_> = chunks.iter().map(|chunk| chunk.len()).collect();
+                assert!(lengths.iter().max().unwrap_or(&0) - lengths.iter().min().unwrap_or(&0) <= 1);
+                // Walking each chunk backward must agree too, so its tail is right
+                let forward: Vec<_> = list.par_iter().threads(threads).chunks().into_iter().flatten().collect();
+                let backward: Vec<_> = chunks
+                    .into_iter()
+                    .flat_map(|chunk| chunk.rev().collect::<Vec<_>>().into_iter().rev())
+                    .collect();
+                assert_eq!(forward, expected, "{} elements on {} threads", length, threads);
+                assert_eq!(backward, expected, "{} elements on {} threads", length, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn map_collect_keeps_order() {
+        let list = list_of(10_000);
+        let expected: Vec<_> = list.iter().map(|element| element * 2 + 1).collect();
+        for threads in [1, 2, 3, 4, 7, 16, 20_000] {
+            let mapped = list.par_iter().threads(threads).map_collect(|element| element * 2 + 1);
+            assert_eq!(mapped, expected, "{} threads", threads);
+        }
+        assert_eq!(list.par_map_collect(|element| element * 2 + 1), expected);
+        assert!(LinkedList::<u32>::new().par_map_collect(|element| *element).is_empty());
+    }
+
+    #[test]
+    fn fold_is_deterministic() {
+        let list = list_of(5_000);
+        let sum: u64 = list.iter().map(|&element| u64::from(element)).sum();
+        let sequence: Vec<u32> = list.iter().copied().collect();
+        for threads in [1, 2, 3, 5, 8, 64] {
+            let parallel = list.par_iter().threads(threads);
+            assert_eq!(parallel.fold(|| 0, |total, &element| total + u64::from(element), |a, b| a + b), sum);
+
+            // Concatenation is associative but not commutative, so this catches misordered chunks
+            let concatenated = list.par_iter().threads(threads).fold(
+                Vec::new,
+                |mut elements, &element| {
+                    elements.push(element);
+                    elements
+                },
+                |mut front, back| {
+                    front.extend(back);
+                    front
+                }
+            );
+            assert_eq!(concatenated, sequence, "{} threads", threads);
+        }
+        assert_eq!(LinkedList::<u32>::new().par_fold(|| 7, |total, _| total + 1, |a, b| a + b), 7);
+    }
+
+    #[test]
+    fn for_each_visits_everything() {
+        let list = list_of(3_000);
+        let visited = AtomicUsize::new(0);
+        let total = AtomicUsize::new(0);
+        let threads = Mutex::new(Vec::new());
+        list.par_iter().threads(4).for_each(|&element| {
+            visited.fetch_add(1, Ordering::Relaxed);
+            total.fetch_add(element as usize, Ordering::Relaxed);
+            let id = thread::current().id();
+            let mut threads = threads.lock().unwrap();
+            if !threads.contains(&id) {
+                threads.push(id);
+            }
+        });
+        assert_eq!(visited.into_inner(), 3_000);
+        assert_eq!(total.into_inner(), list.iter().map(|&element| element as usize).sum());
+        assert_eq!(threads.into_inner().unwrap().len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "bad element")]
+    fn panics_reach_the_caller() {
+        let list = list_of(100);
+        let last = *list.back().unwrap();
+        list.par_iter().threads(4).for_each(|&element| {
+            if element == last {
+                panic!("bad element");
+            }
+        });
+    }
+}
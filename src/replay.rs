@@ -0,0 +1,501 @@
+//! Replays recorded traces of deque operations against any of the crate's lists, for
+//! reproducing production issues and for spotting where two implementations disagree.
+//!
+//! A trace is one operation per line: `push_front <n>`, `push_back <n>`, `pop_front`,
+//! `pop_back`, `front`, `back` or `len`, with `i64` elements. Blank lines and lines starting
+//! with `#` are skipped. Operations a list doesn't offer, like `pop_back` on the stacks, come
+//! out as `Outcome::Unsupported` rather than failing the replay.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::format;
+use alloc::collections::VecDeque;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+use crate::{fifth, first, fourth, second, sixth, third};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    PushFront(i64),
+    PushBack(i64),
+    PopFront,
+    PopBack,
+    Front,
+    Back,
+    Len
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Op, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("empty operation")?;
+        let mut element = || -> Result<i64, String> {
+            let word = words.next().ok_or_else(|| format!("`{}` needs an element", name))?;
+            word.parse().map_err(|_| format!("`{}` isn't an integer element", word))
+        };
+        let op = match name {
+            "push_front" => Op::PushFront(element()?),
+            "push_back" => Op::PushBack(element()?),
+            "pop_front" => Op::PopFront,
+            "pop_back" => Op::PopBack,
+            "front" => Op::Front,
+            "back" => Op::Back,
+            "len" => Op::Len,
+            _ => return Err(format!("unknown operation `{}`", name))
+        };
+        match words.next() {
+            Some(extra) => Err(format!("unexpected `{}` after `{}`", extra, name)),
+            None => Ok(op)
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::PushFront(element) => write!(f, "push_front {}", element),
+            Op::PushBack(element) => write!(f, "push_back {}", element),
+            Op::PopFront => f.write_str("pop_front"),
+            Op::PopBack => f.write_str("pop_back"),
+            Op::Front => f.write_str("front"),
+            Op::Back => f.write_str("back"),
+            Op::Len => f.write_str("len")
+        }
+    }
+}
+
+/// A malformed trace line; `line` counts from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses a whole trace, pairing each operation with its line number.
+pub fn parse(trace: &str) -> Result<Vec<(usize, Op)>, ParseError> {
+    trace
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line, text)| text.parse().map(|op| (line, op)).map_err(|message| ParseError { line, message }))
+        .collect()
+}
+
+/// What one operation produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Done,
+    Element(Option<i64>),
+    Len(usize),
+    Unsupported
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Done => f.write_str("ok"),
+            Outcome::Element(Some(element)) => write!(f, "{}", element),
+            Outcome::Element(None) => f.write_str("none"),
+            Outcome::Len(len) => write!(f, "{}", len),
+            Outcome::Unsupported => f.write_str("unsupported")
+        }
+    }
+}
+
+/// A list a trace can drive. Each operation returns `None` if the list doesn't offer it.
+pub trait Target {
+    fn push_front(&mut self, _element: i64) -> Option<()> {
+        None
+    }
+
+    fn push_back(&mut self, _element: i64) -> Option<()> {
+        None
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        None
+    }
+
+    fn pop_back(&mut self) -> Option<Option<i64>> {
+        None
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        None
+    }
+
+    fn back(&self) -> Option<Option<i64>> {
+        None
+    }
+
+    fn length(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Runs `op` against `target`.
+pub fn apply(target: &mut dyn Target, op: Op) -> Outcome {
+    let outcome = match op {
+        Op::PushFront(element) => target.push_front(element).map(|()| Outcome::Done),
+        Op::PushBack(element) => target.push_back(element).map(|()| Outcome::Done),
+        Op::PopFront => target.pop_front().map(Outcome::Element),
+        Op::PopBack => target.pop_back().map(Outcome::Element),
+        Op::Front => target.front().map(Outcome::Element),
+        Op::Back => target.back().map(Outcome::Element),
+        Op::Len => target.length().map(Outcome::Len)
+    };
+    outcome.unwrap_or(Outcome::Unsupported)
+}
+
+/// The implementations a trace can be replayed against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Implementation {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    /// `std::collections::VecDeque`, as a reference to diff the others against.
+    VecDeque
+}
+
+impl Implementation {
+    pub const ALL: [Implementation; 7] = [
+        Implementation::First,
+        Implementation::Second,
+        Implementation::Third,
+        Implementation::Fourth,
+        Implementation::Fifth,
+        Implementation::Sixth,
+        Implementation::VecDeque
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Implementation::First => "first",
+            Implementation::Second => "second",
+            Implementation::Third => "third",
+            Implementation::Fourth => "fourth",
+            Implementation::Fifth => "fifth",
+            Implementation::Sixth => "sixth",
+            Implementation::VecDeque => "vecdeque"
+        }
+    }
+
+    /// Creates an empty list of this implementation.
+    pub fn target(self) -> Box<dyn Target> {
+        match self {
+            Implementation::First => Box::new(first::List::new()),
+            Implementation::Second => Box::new(second::List::new()),
+            Implementation::Third => Box::new(third::List::new()),
+            Implementation::Fourth => Box::new(fourth::List::new()),
+            Implementation::Fifth => Box::new(fifth::List::new()),
+            Implementation::Sixth => Box::new(sixth::LinkedList::new()),
+            Implementation::VecDeque => Box::new(VecDeque::new())
+        }
+    }
+}
+
+impl FromStr for Implementation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Implementation, String> {
+        Implementation::ALL
+            .into_iter()
+            .find(|implementation| implementation.name() == name)
+            .ok_or_else(|| format!("unknown implementation `{}`", name))
+    }
+}
+
+impl fmt::Display for Implementation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Replays `ops` against a fresh list of `implementation`, returning each operation's outcome.
+pub fn run(implementation: Implementation, ops: &[(usize, Op)]) -> Vec<Outcome> {
+    let mut target = implementation.target();
+    ops.iter().map(|&(_, op)| apply(&mut *target, op)).collect()
+}
+
+/// An operation two implementations produced different outcomes for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub line: usize,
+    pub op: Op,
+    pub left: Outcome,
+    pub right: Outcome
+}
+
+/// Replays `ops` against both implementations and lists every operation where they disagree.
+pub fn diff(left: Implementation, right: Implementation, ops: &[(usize, Op)]) -> Vec<Divergence> {
+    run(left, ops)
+        .into_iter()
+        .zip(run(right, ops))
+        .zip(ops)
+        .filter(|((left, right), _)| left != right)
+        .map(|((left, right), &(line, op))| Divergence { line, op, left, right })
+        .collect()
+}
+
+impl Target for first::List<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        self.push(element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(self.pop())
+    }
+}
+
+impl Target for second::List<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        self.push(element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(self.pop())
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(self.peek().copied())
+    }
+}
+
+impl Target for third::List<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        *self = self.prepend(element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        let head = self.iter().next().copied();
+        *self = self.tail();
+        Some(head)
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(self.iter().next().copied())
+    }
+}
+
+impl Target for fourth::List<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        self.push_head(element);
+        Some(())
+    }
+
+    fn push_back(&mut self, element: i64) -> Option<()> {
+        self.push_tail(element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(self.pop_head())
+    }
+
+    fn pop_back(&mut self) -> Option<Option<i64>> {
+        Some(self.pop_tail())
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(self.peek_head().map(|element| *element))
+    }
+
+    fn back(&self) -> Option<Option<i64>> {
+        Some(self.peek_tail().map(|element| *element))
+    }
+}
+
+impl Target for fifth::List<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        fifth::List::push_front(self, element);
+        Some(())
+    }
+
+    fn push_back(&mut self, element: i64) -> Option<()> {
+        self.push(element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(self.pop())
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(self.peek().copied())
+    }
+
+    fn back(&self) -> Option<Option<i64>> {
+        Some(fifth::List::back(self).copied())
+    }
+
+    fn length(&self) -> Option<usize> {
+        Some(fifth::List::len(self))
+    }
+}
+
+impl Target for sixth::LinkedList<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        sixth::LinkedList::push_front(self, element);
+        Some(())
+    }
+
+    fn push_back(&mut self, element: i64) -> Option<()> {
+        sixth::LinkedList::push_back(self, element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(sixth::LinkedList::pop_front(self))
+    }
+
+    fn pop_back(&mut self) -> Option<Option<i64>> {
+        Some(sixth::LinkedList::pop_back(self))
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(sixth::LinkedList::front(self).copied())
+    }
+
+    fn back(&self) -> Option<Option<i64>> {
+        Some(sixth::LinkedList::back(self).copied())
+    }
+
+    fn length(&self) -> Option<usize> {
+        Some(sixth::LinkedList::len(self))
+    }
+}
+
+impl Target for VecDeque<i64> {
+    fn push_front(&mut self, element: i64) -> Option<()> {
+        VecDeque::push_front(self, element);
+        Some(())
+    }
+
+    fn push_back(&mut self, element: i64) -> Option<()> {
+        VecDeque::push_back(self, element);
+        Some(())
+    }
+
+    fn pop_front(&mut self) -> Option<Option<i64>> {
+        Some(VecDeque::pop_front(self))
+    }
+
+    fn pop_back(&mut self) -> Option<Option<i64>> {
+        Some(VecDeque::pop_back(self))
+    }
+
+    fn front(&self) -> Option<Option<i64>> {
+        Some(VecDeque::front(self).copied())
+    }
+
+    fn back(&self) -> Option<Option<i64>> {
+        Some(VecDeque::back(self).copied())
+    }
+
+    fn length(&self) -> Option<usize> {
+        Some(VecDeque::len(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, parse, run, Implementation, Op, Outcome, ParseError};
+
+    const TRACE: &str = "
+        # a queue that's drained from both ends
+        push_back 1
+        push_back 2
+        push_front 0
+        front
+        back
+        len
+        pop_back
+        pop_front
+        pop_front
+        pop_front
+    ";
+
+    #[test]
+    fn parses_traces() {
+        let ops = parse("push_front -5\n\n  pop_back  \n# comment\nlen").unwrap();
+        assert_eq!(ops, vec![(1, Op::PushFront(-5)), (3, Op::PopBack), (5, Op::Len)]);
+        assert_eq!(Op::PushBack(7).to_string().parse(), Ok(Op::PushBack(7)));
+
+        let error = |trace| parse(trace).unwrap_err();
+        assert_eq!(error("front\nshove 3"), ParseError { line: 2, message: "unknown operation `shove`".into() });
+        assert_eq!(error("push_back").message, "`push_back` needs an element");
+        assert_eq!(error("push_back x").message, "`x` isn't an integer element");
+        assert_eq!(error("len 3").to_string(), "line 1: unexpected `3` after `len`");
+    }
+
+    #[test]
+    fn full_deques_agree() {
+        let ops = parse(TRACE).unwrap();
+        let expected = run(Implementation::VecDeque, &ops);
+        assert_eq!(
+            expected,
+            vec![
+                Outcome::Done,
+                Outcome::Done,
+                Outcome::Done,
+                Outcome::Element(Some(0)),
+                Outcome::Element(Some(2)),
+                Outcome::Len(3),
+                Outcome::Element(Some(2)),
+                Outcome::Element(Some(0)),
+                Outcome::Element(Some(1)),
+                Outcome::Element(None)
+            ]
+        );
+        assert_eq!(run(Implementation::Sixth, &ops), expected);
+        assert!(diff(Implementation::Sixth, Implementation::VecDeque, &ops).is_empty());
+    }
+
+    #[test]
+    fn stacks_agree_on_the_front() {
+        let ops = parse("push_front 1\npush_front 2\nfront\npop_front\npop_front\npop_front").unwrap();
+        let expected = run(Implementation::VecDeque, &ops);
+        for implementation in Implementation::ALL {
+            assert_eq!(run(implementation, &ops)[3..], expected[3..], "{} diverged", implementation);
+        }
+        // `first` can't peek, but everything else matches
+        let divergences = diff(Implementation::First, Implementation::Second, &ops);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].line, 3);
+        assert_eq!((divergences[0].left, divergences[0].right), (Outcome::Unsupported, Outcome::Element(Some(2))));
+    }
+
+    #[test]
+    fn missing_operations_diverge() {
+        let ops = parse(TRACE).unwrap();
+        let divergences = diff(Implementation::Fourth, Implementation::Sixth, &ops);
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].op, Op::Len);
+        assert_eq!(divergences[0].left.to_string(), "unsupported");
+
+        let lines: Vec<_> = diff(Implementation::Fifth, Implementation::Sixth, &ops).iter().map(|d| d.line).collect();
+        // Missing `pop_back` leaves an extra element behind, which the last `pop_front` then finds
+        assert_eq!(lines, vec![9, 12]);
+        assert_eq!(Implementation::ALL.map(|i| i.name().parse()), Implementation::ALL.map(Ok));
+        assert!("seventh".parse::<Implementation>().is_err());
+    }
+}
@@ -1,8 +1,8 @@
-use std::mem;
+use alloc::boxed::Box;
 
 #[derive(Debug)]
 pub struct List<T> {
-    head: Link<T>
+    pub(crate) head: Link<T>
 }
 
 impl<T> List<T> {
@@ -33,26 +33,45 @@ impl<T> List<T> {
         })
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter { next: self.head.as_deref() }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut { next: self.head.as_deref_mut() }
     }
 
+    /// Reverses the list in place by relinking its nodes.
+    pub fn reverse(&mut self) {
+        let mut reversed: Link<T> = None;
+        let mut current = self.head.take();
+        while let Some(mut boxed_node) = current {
+            current = boxed_node.next.take();
+            boxed_node.next = reversed;
+            reversed = Some(boxed_node);
+        }
+        self.head = reversed;
+    }
+
     pub fn new() -> Self {
         List { head: None }
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
-        let mut cur_link = mem::replace(&mut self.head, None);
+        let mut cur_link = self.head.take();
         // `while let` == "do this thing until this pattern doesn't match"
         while let Some(mut boxed_node) = cur_link {
             cur_link = boxed_node.next.take();
@@ -65,6 +84,14 @@ impl<T> Drop for List<T> {
 
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
@@ -101,14 +128,42 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 }
 
 #[derive(Debug)]
-struct Node<T> {
-    element: T,
-    next: Link<T>
+pub(crate) struct Node<T> {
+    pub(crate) element: T,
+    pub(crate) next: Link<T>
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+pub(crate) type Link<T> = Option<Box<Node<T>>>;
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_support::deserialize_seq(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> crate::serde_support::FromSeq<'de> for List<T> {
+    fn from_seq<S: serde::de::SeqAccess<'de>>(mut seq: S) -> Result<Self, S::Error> {
+        let mut list = List::new();
+        // Fill in the last `next` each time so the elements keep their sequence order
+        let mut cursor = &mut list.head;
+        while let Some(element) = seq.next_element()? {
+            cursor = &mut cursor.insert(Box::new(Node { element, next: None })).next;
+        }
+        Ok(list)
+    }
+}
 
 #[cfg(test)]
+#[allow(clippy::clone_on_copy, clippy::explicit_counter_loop, clippy::needless_borrow, clippy::option_map_unit_fn)]
 mod test {
     use super::List;
     #[test]
@@ -210,4 +265,42 @@ mod test {
         assert_eq!(list.peek(), Some(&42));
         assert_eq!(list.pop(), Some(42));
     }
+
+    #[test]
+    fn reverse() {
+        let mut list = List::new();
+        list.reverse();
+        assert_eq!(list.peek(), None);
+
+        list.push(1); list.push(2); list.push(3);
+        list.reverse();
+
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::serde_support::json;
+
+        let mut list = List::new();
+        list.push(3);
+        list.push(2);
+        list.push(1);
+        let text = json::to_string(&list).unwrap();
+        assert_eq!(text, "[1,2,3]");
+
+        let mut decoded: List<i32> = json::from_str(&text).unwrap();
+        assert_eq!(decoded.pop(), Some(1));
+        assert_eq!(decoded.pop(), Some(2));
+        assert_eq!(decoded.pop(), Some(3));
+        assert_eq!(decoded.pop(), None);
+
+        let empty: List<i32> = json::from_str("[]").unwrap();
+        assert_eq!(empty.peek(), None);
+        assert!(json::from_str::<List<i32>>("5").is_err());
+    }
 }
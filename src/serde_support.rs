@@ -0,0 +1,344 @@
+//! Shared plumbing for the lists' `serde` impls, which serialize every list as a sequence in
+//! its logical order.
+//!
+//! Deserializing builds the list straight from the `SeqAccess`, one element at a time, so no
+//! intermediate `Vec` is ever collected.
+
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{Deserializer, SeqAccess, Visitor};
+
+/// A list that can be built in order from a stream of sequence elements.
+pub(crate) trait FromSeq<'de>: Sized {
+    fn from_seq<S: SeqAccess<'de>>(seq: S) -> Result<Self, S::Error>;
+}
+
+pub(crate) fn deserialize_seq<'de, L, D>(deserializer: D) -> Result<L, D::Error>
+where L: FromSeq<'de>, D: Deserializer<'de> {
+    deserializer.deserialize_seq(SeqVisitor(PhantomData))
+}
+
+struct SeqVisitor<L>(PhantomData<L>);
+
+impl<'de, L: FromSeq<'de>> Visitor<'de> for SeqVisitor<L> {
+    type Value = L;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, seq: S) -> Result<L, S::Error> {
+        L::from_seq(seq)
+    }
+}
+
+/// A tiny JSON subset for round-trip tests: integers, booleans, strings without escapes other
+/// than `\"` and `\\`, `null`, and arrays.
+#[cfg(test)]
+pub(crate) mod json {
+    use std::error;
+    use std::fmt;
+    use serde::de::{self, DeserializeSeed, Deserializer, IntoDeserializer, SeqAccess, Visitor};
+    use serde::ser::{self, Impossible, Serialize, SerializeSeq, Serializer};
+
+    #[derive(Debug, PartialEq)]
+    pub struct Error(pub String);
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl error::Error for Error {}
+
+    impl ser::Error for Error {
+        fn custom<M: fmt::Display>(message: M) -> Self {
+            Error(message.to_string())
+        }
+    }
+
+    impl de::Error for Error {
+        fn custom<M: fmt::Display>(message: M) -> Self {
+            Error(message.to_string())
+        }
+    }
+
+    pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+        let mut output = String::new();
+        value.serialize(&mut Writer { output: &mut output })?;
+        Ok(output)
+    }
+
+    pub fn from_str<'de, T: de::Deserialize<'de>>(input: &'de str) -> Result<T, Error> {
+        let mut reader = Reader { input };
+        let value = T::deserialize(&mut reader)?;
+        reader.skip_whitespace();
+        if reader.input.is_empty() {
+            Ok(value)
+        } else {
+            Err(Error(format!("trailing characters: {:?}", reader.input)))
+        }
+    }
+
+    struct Writer<'a> {
+        output: &'a mut String
+    }
+
+    fn unsupported<T>(what: &str) -> Result<T, Error> {
+        Err(Error(format!("the test format can't serialize {}", what)))
+    }
+
+    impl Serializer for &mut Writer<'_> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Self;
+        type SerializeTuple = Impossible<(), Error>;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Impossible<(), Error>;
+        type SerializeStruct = Impossible<(), Error>;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), Error> {
+            self.output.push_str(if v { "true" } else { "false" });
+            Ok(())
+        }
+
+        fn serialize_i8(self, v: i8) -> Result<(), Error> { self.serialize_i64(v.into()) }
+        fn serialize_i16(self, v: i16) -> Result<(), Error> { self.serialize_i64(v.into()) }
+        fn serialize_i32(self, v: i32) -> Result<(), Error> { self.serialize_i64(v.into()) }
+        fn serialize_u8(self, v: u8) -> Result<(), Error> { self.serialize_u64(v.into()) }
+        fn serialize_u16(self, v: u16) -> Result<(), Error> { self.serialize_u64(v.into()) }
+        fn serialize_u32(self, v: u32) -> Result<(), Error> { self.serialize_u64(v.into()) }
+
+        fn serialize_i64(self, v: i64) -> Result<(), Error> {
+            self.output.push_str(&v.to_string());
+            Ok(())
+        }
+
+        fn serialize_u64(self, v: u64) -> Result<(), Error> {
+            self.output.push_str(&v.to_string());
+            Ok(())
+        }
+
+        fn serialize_f32(self, _: f32) -> Result<(), Error> { unsupported("floats") }
+        fn serialize_f64(self, _: f64) -> Result<(), Error> { unsupported("floats") }
+
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            self.serialize_str(v.encode_utf8(&mut [0; 4]))
+        }
+
+        fn serialize_str(self, v: &str) -> Result<(), Error> {
+            self.output.push('"');
+            for c in v.chars() {
+                if c == '"' || c == '\\' {
+                    self.output.push('\\');
+                }
+                self.output.push(c);
+            }
+            self.output.push('"');
+            Ok(())
+        }
+
+        fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> { unsupported("bytes") }
+
+        fn serialize_none(self) -> Result<(), Error> { self.serialize_unit() }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<(), Error> {
+            self.output.push_str("null");
+            Ok(())
+        }
+
+        fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> { self.serialize_unit() }
+
+        fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<(), Error> {
+            unsupported("enums")
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self, _: &'static str, _: u32, _: &'static str, _: &T
+        ) -> Result<(), Error> {
+            unsupported("enums")
+        }
+
+        fn serialize_seq(self, _: Option<usize>) -> Result<Self, Error> {
+            self.output.push('[');
+            Ok(self)
+        }
+
+        fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> { unsupported("tuples") }
+
+        fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, Error> {
+            unsupported("tuples")
+        }
+
+        fn serialize_tuple_variant(
+            self, _: &'static str, _: u32, _: &'static str, _: usize
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            unsupported("enums")
+        }
+
+        fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> { unsupported("maps") }
+
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+            unsupported("structs")
+        }
+
+        fn serialize_struct_variant(
+            self, _: &'static str, _: u32, _: &'static str, _: usize
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            unsupported("enums")
+        }
+    }
+
+    impl SerializeSeq for &mut Writer<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            if !self.output.ends_with('[') {
+                self.output.push(',');
+            }
+            value.serialize(&mut **self)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.output.push(']');
+            Ok(())
+        }
+    }
+
+    struct Reader<'de> {
+        input: &'de str
+    }
+
+    impl<'de> Reader<'de> {
+        fn skip_whitespace(&mut self) {
+            self.input = self.input.trim_start();
+        }
+
+        fn peek(&mut self) -> Result<char, Error> {
+            self.skip_whitespace();
+            self.input.chars().next().ok_or_else(|| Error("unexpected end of input".into()))
+        }
+
+        fn eat(&mut self, expected: char) -> Result<(), Error> {
+            match self.peek()? {
+                c if c == expected => {
+                    self.input = &self.input[c.len_utf8()..];
+                    Ok(())
+                }
+                c => Err(Error(format!("expected {:?}, found {:?}", expected, c)))
+            }
+        }
+
+        fn eat_word(&mut self, word: &str) -> Result<(), Error> {
+            match self.input.strip_prefix(word) {
+                Some(rest) => {
+                    self.input = rest;
+                    Ok(())
+                }
+                None => Err(Error(format!("expected {}", word)))
+            }
+        }
+
+        fn string(&mut self) -> Result<String, Error> {
+            self.eat('"')?;
+            let mut string = String::new();
+            let mut chars = self.input.char_indices();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        self.input = &self.input[i + 1..];
+                        return Ok(string);
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, escaped)) => string.push(escaped),
+                        None => break
+                    },
+                    c => string.push(c)
+                }
+            }
+            Err(Error("unterminated string".into()))
+        }
+    }
+
+    impl<'de> Deserializer<'de> for &mut Reader<'de> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.peek()? {
+                '[' => {
+                    self.eat('[')?;
+                    let value = visitor.visit_seq(Elements { reader: &mut *self, first: true })?;
+                    self.eat(']')?;
+                    Ok(value)
+                }
+                '"' => visitor.visit_string(self.string()?),
+                't' => self.eat_word("true").and_then(|_| visitor.visit_bool(true)),
+                'f' => self.eat_word("false").and_then(|_| visitor.visit_bool(false)),
+                'n' => self.eat_word("null").and_then(|_| visitor.visit_unit()),
+                '-' | '0'..='9' => {
+                    let end = self.input[1..]
+                        .find(|c: char| !c.is_ascii_digit())
+                        .map_or(self.input.len(), |end| end + 1);
+                    let (number, rest) = self.input.split_at(end);
+                    self.input = rest;
+                    if number.starts_with('-') {
+                        visitor.visit_i64(number.parse().map_err(|_| Error(format!("bad number {}", number)))?)
+                    } else {
+                        visitor.visit_u64(number.parse().map_err(|_| Error(format!("bad number {}", number)))?)
+                    }
+                }
+                c => Err(Error(format!("unexpected {:?}", c)))
+            }
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            if self.peek()? == 'n' {
+                self.eat_word("null")?;
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(self)
+            }
+        }
+
+        fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.string()?.into_deserializer().deserialize_char(visitor)
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 str string bytes byte_buf unit
+            unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    struct Elements<'a, 'de> {
+        reader: &'a mut Reader<'de>,
+        first: bool
+    }
+
+    impl<'de> SeqAccess<'de> for Elements<'_, 'de> {
+        type Error = Error;
+
+        fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+            if self.reader.peek()? == ']' {
+                return Ok(None);
+            }
+            if !self.first {
+                self.reader.eat(',')?;
+            }
+            self.first = false;
+            seed.deserialize(&mut *self.reader).map(Some)
+        }
+    }
+}
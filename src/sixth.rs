@@ -1,33 +1,116 @@
-use std::marker::PhantomData;
-use std::ptr::NonNull;
+#[cfg(any(test, feature = "debug-invariants"))]
+use alloc::collections::BTreeSet;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use crate::allocator::{self, Allocator, Global};
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::InvariantError;
 
-pub struct LinkedList<T> {
+pub struct LinkedList<T, A: Allocator = Global> {
     head: Link<T>,
     tail: Link<T>,
     length: usize,
+    // Freed nodes kept for reuse, chained through `next`. Their elements are uninitialized.
+    cache: Link<T>,
+    cache_len: usize,
+    cache_capacity: usize,
+    allocator: A,
     _boo: PhantomData<T>
 }
 
-type Link<T> = Option<NonNull<Node<T>>>;
+pub(crate) type Link<T> = Option<NonNull<Node<T>>>;
 
-struct Node<T> {
-    previous: Link<T>,
-    element: T,
-    next: Link<T>
+pub(crate) struct Node<T> {
+    pub(crate) previous: Link<T>,
+    pub(crate) element: T,
+    pub(crate) next: Link<T>
 }
 
+// `NonNull` opts out of `Send` and `Sync`, but each node belongs to one list and is only reached through it
+unsafe impl<T: Send, A: Allocator + Send> Send for LinkedList<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for LinkedList<T, A> {}
+
 impl<T> LinkedList<T> {
     pub fn new() -> LinkedList<T> {
-        LinkedList { head: None, tail: None, length: 0, _boo: PhantomData }
+        LinkedList::new_in(Global)
+    }
+
+    /// Creates a list that keeps up to `capacity` popped nodes around and reuses them for
+    /// later pushes instead of going back to the allocator.
+    pub fn with_node_cache(capacity: usize) -> LinkedList<T> {
+        LinkedList::with_node_cache_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> LinkedList<T, A> {
+    /// Creates a list whose nodes are allocated and freed through `allocator`.
+    pub fn new_in(allocator: A) -> LinkedList<T, A> {
+        LinkedList::with_node_cache_in(0, allocator)
+    }
+
+    pub fn with_node_cache_in(capacity: usize, allocator: A) -> LinkedList<T, A> {
+        LinkedList {
+            head: None,
+            tail: None,
+            length: 0,
+            cache: None,
+            cache_len: 0,
+            cache_capacity: capacity,
+            allocator,
+            _boo: PhantomData
+        }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    /// How many freed nodes are currently waiting to be reused.
+    pub fn node_cache_len(&self) -> usize {
+        self.cache_len
+    }
+
+    /// Returns every cached node to the allocator. The cache keeps its capacity and will
+    /// fill up again as elements are popped.
+    pub fn shrink_node_cache(&mut self) {
+        while let Some(cached) = self.cache {
+            unsafe {
+                self.cache = (*cached.as_ptr()).next;
+                allocator::deallocate_node(&self.allocator, cached);
+            }
+        }
+        self.cache_len = 0;
+    }
+
+    fn alloc_node(&mut self, element: T) -> NonNull<Node<T>> {
+        let node = Node { previous: None, element, next: None };
+        match self.cache {
+            Some(cached) => unsafe {
+                self.cache = (*cached.as_ptr()).next;
+                self.cache_len -= 1;
+                cached.as_ptr().write(node);
+                cached
+            },
+            None => allocator::allocate_node(&self.allocator, node)
+        }
+    }
+
+    // Moves the element out of an already unlinked node, then recycles or frees the node.
+    unsafe fn free_node(&mut self, node: NonNull<Node<T>>) -> T {
+        let element = ptr::addr_of!((*node.as_ptr()).element).read();
+        if self.cache_len < self.cache_capacity {
+            (*node.as_ptr()).next = self.cache;
+            self.cache = Some(node);
+            self.cache_len += 1;
+        } else {
+            allocator::deallocate_node(&self.allocator, node);
+        }
+        element
     }
 
     pub fn push_front(&mut self, element: T) {
+        let new_head = self.alloc_node(element);
         unsafe {
-            let new_head = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                previous: None,
-                element,
-                next: None
-            })));
             if let Some(old_head) = self.head {
                 (*new_head.as_ptr()).next = Some(old_head);
                 (*old_head.as_ptr()).previous = Some(new_head);
@@ -41,12 +124,8 @@ impl<T> LinkedList<T> {
     }
 
     pub fn push_back(&mut self, element: T) {
+        let new_tail = self.alloc_node(element);
         unsafe {
-            let new_tail = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                previous: None,
-                element,
-                next: None
-            })));
             if let Some(old_tail) = self.tail {
                 (*new_tail.as_ptr()).previous = Some(old_tail);
                 (*old_tail.as_ptr()).next = Some(new_tail);
@@ -62,17 +141,15 @@ impl<T> LinkedList<T> {
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.map(|head_node| {
             unsafe {
-                let old_head = Box::from_raw(head_node.as_ptr());
-                self.head = old_head.next.map(|new_head| {
+                self.head = (*head_node.as_ptr()).next.inspect(|new_head| {
                     (*new_head.as_ptr()).previous = None;
-                    new_head
                 }).or_else(|| {
                     self.tail = None;
                     None
                 });
 
                 self.length -= 1;
-                old_head.element
+                self.free_node(head_node)
             }
         })
     }
@@ -80,17 +157,15 @@ impl<T> LinkedList<T> {
     pub fn pop_back(&mut self) -> Option<T> {
         self.tail.map(|tail_node| {
             unsafe {
-                let old_tail = Box::from_raw(tail_node.as_ptr());
-                self.tail = old_tail.previous.map(|new_tail| {
+                self.tail = (*tail_node.as_ptr()).previous.inspect(|new_tail| {
                     (*new_tail.as_ptr()).next = None;
-                    new_tail
                 }).or_else(|| {
                     self.head = None;
                     None
                 });
 
                 self.length -= 1;
-                old_tail.element
+                self.free_node(tail_node)
             }
         })
     }
@@ -123,7 +198,16 @@ impl<T> LinkedList<T> {
         self.length
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter { list: self }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             head: self.head,
             tail: self.tail,
@@ -132,18 +216,179 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter { list: self }
+    /// Reverses the list in place by swapping every node's links in a single pass.
+    pub fn reverse(&mut self) {
+        let mut current = self.head;
+        while let Some(node) = current {
+            unsafe {
+                let node = &mut *node.as_ptr();
+                current = node.next;
+                core::mem::swap(&mut node.previous, &mut node.next);
+            }
+        }
+        core::mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    /// Borrows the list back to front, without touching any links.
+    pub fn reversed(&mut self) -> ReversedView<'_, T, A> {
+        ReversedView { list: self }
+    }
+
+    /// Like `reversed`, but read-only, so it only needs a shared borrow.
+    pub fn reversed_ref(&self) -> ReversedRef<'_, T, A> {
+        ReversedRef { list: self }
+    }
+}
+
+impl<T> Node<T> {
+    pub(crate) unsafe fn element<'a>(node: NonNull<Node<T>>) -> &'a T {
+        &(*node.as_ptr()).element
+    }
+
+    pub(crate) unsafe fn next(node: NonNull<Node<T>>) -> Link<T> {
+        (*node.as_ptr()).next
+    }
+
+    pub(crate) unsafe fn previous(node: NonNull<Node<T>>) -> Link<T> {
+        (*node.as_ptr()).previous
+    }
+}
+
+// Node-level access for collections in this crate that relink nodes directly, such as
+// `sorted::SortedList`. Callers keep the list's links and `length` consistent.
+impl<T, A: Allocator> LinkedList<T, A> {
+    pub(crate) fn head_node(&self) -> Link<T> {
+        self.head
+    }
+
+    pub(crate) fn tail_node(&self) -> Link<T> {
+        self.tail
+    }
+
+    /// Inserts `element` before `next`, or at the back if `next` is `None`.
+    pub(crate) unsafe fn insert_before(&mut self, next: Link<T>, element: T) {
+        let node = self.alloc_node(element);
+        self.splice_before(next, node, node, 1);
+    }
+
+    /// Links the chain `first..=last` of `count` nodes in before `next`, or at the back if
+    /// `next` is `None`. The chain's nodes must have come from an allocator this list can
+    /// free them with.
+    pub(crate) unsafe fn splice_before(
+        &mut self,
+        next: Link<T>,
+        first: NonNull<Node<T>>,
+        last: NonNull<Node<T>>,
+        count: usize
+    ) {
+        let previous = match next {
+            Some(next) => (*next.as_ptr()).previous,
+            None => self.tail
+        };
+        (*first.as_ptr()).previous = previous;
+        (*last.as_ptr()).next = next;
+        match previous {
+            Some(previous) => (*previous.as_ptr()).next = Some(first),
+            None => self.head = Some(first)
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).previous = Some(last),
+            None => self.tail = Some(last)
+        }
+        self.length += count;
+    }
+
+    /// Unlinks `node`, which must belong to this list, and returns its element.
+    pub(crate) unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) -> T {
+        let Node { previous, next, .. } = *node.as_ptr();
+        match previous {
+            Some(previous) => (*previous.as_ptr()).next = next,
+            None => self.head = next
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).previous = previous,
+            None => self.tail = previous
+        }
+        self.length -= 1;
+        self.free_node(node)
+    }
+
+    /// Empties the list without freeing anything and returns its old head; the nodes stay
+    /// chained together for the caller to splice elsewhere.
+    pub(crate) fn detach_all(&mut self) -> Link<T> {
+        self.tail = None;
+        self.length = 0;
+        self.head.take()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, A: Allocator> LinkedList<T, A> {
+    /// Walks the list forward and backward and checks that its links and `length` agree.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        if self.head.is_none() != self.tail.is_none() {
+            return Err(InvariantError::MismatchedEnds);
+        }
+        unsafe {
+            if self.head.is_some_and(|head| (*head.as_ptr()).previous.is_some()) {
+                return Err(InvariantError::HeadHasPrevious);
+            }
+            if self.tail.is_some_and(|tail| (*tail.as_ptr()).next.is_some()) {
+                return Err(InvariantError::TailHasNext);
+            }
+
+            // Walk forward along the `next` links until they run out or loop back
+            let mut visited = BTreeSet::new();
+            let mut last = None;
+            let mut current = self.head;
+            while let Some(node) = current {
+                if !visited.insert(node) {
+                    return Err(InvariantError::Cycle { index: visited.len() - 1 });
+                }
+                last = current;
+                current = (*node.as_ptr()).next;
+            }
+            if last != self.tail {
+                return Err(InvariantError::TailUnreachable);
+            }
+
+            // Walk back along the `previous` links, checking each one leads to a node whose `next` is us
+            let forward = visited.len();
+            let mut current = self.tail;
+            for index in (1..forward).rev() {
+                let previous = (*current.unwrap().as_ptr()).previous;
+                if previous.is_none_or(|previous| (*previous.as_ptr()).next != current) {
+                    return Err(InvariantError::BrokenBackLink { index });
+                }
+                current = previous;
+            }
+            if current != self.head {
+                return Err(InvariantError::HeadUnreachable);
+            }
+            if forward != self.length {
+                return Err(InvariantError::LengthMismatch { length: self.length, counted: forward });
+            }
+        }
+        Ok(())
     }
 }
 
-impl<T> Drop for LinkedList<T> {
+impl<T, A: Allocator + Default> Default for LinkedList<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T, A: Allocator> Drop for LinkedList<T, A> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop_front() {};
+        // Skip the cache so every node goes straight back to the allocator
+        self.cache_capacity = 0;
+        while self.pop_front().is_some() {}
+        self.shrink_node_cache();
     }
 }
 
-impl<'a, T> IntoIterator for &'a LinkedList<T> {
+impl<'a, T, A: Allocator> IntoIterator for &'a LinkedList<T, A> {
     type Item = &'a T;
     type IntoIter = Iter<'a, T>;
 
@@ -159,6 +404,18 @@ pub struct Iter<'a, T> {
     _boo: PhantomData<&'a T>
 }
 
+// Like `&T`, an iterator of shared references can cross threads whenever `T` is `Sync`
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+impl<'a, T> Iter<'a, T> {
+    /// Iterates over the `length` nodes from `head` through `tail`, which must stay alive
+    /// and unchanged for `'a`.
+    pub(crate) unsafe fn new(head: Link<T>, tail: Link<T>, length: usize) -> Self {
+        Iter { head, tail, length, _boo: PhantomData }
+    }
+}
+
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
@@ -204,47 +461,292 @@ impl<'a, T> ExactSizeIterator for Iter<'a, T> {
     }
 }
 
-pub struct IntoIter<T> {
-    list: LinkedList<T>
+pub struct IntoIter<T, A: Allocator = Global> {
+    list: LinkedList<T, A>
 }
 
-impl<T> IntoIterator for LinkedList<T> {
+impl<T, A: Allocator> IntoIterator for LinkedList<T, A> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, A>;
     fn into_iter(self) -> Self::IntoIter {
         self.into_iter()
     }
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
        self.list.pop_front()
     }
 }
 
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.list.pop_back()
     }
 }
 
+/// The double-ended operations `LinkedList` and `ReversedView` share, so code written against
+/// one direction can be handed either.
+pub trait Deque<T> {
+    fn push_front(&mut self, element: T);
+    fn push_back(&mut self, element: T);
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+    fn front(&self) -> Option<&T>;
+    fn front_mut(&mut self) -> Option<&mut T>;
+    fn back(&self) -> Option<&T>;
+    fn back_mut(&mut self) -> Option<&mut T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T, A: Allocator> Deque<T> for LinkedList<T, A> {
+    fn push_front(&mut self, element: T) {
+        LinkedList::push_front(self, element)
+    }
+
+    fn push_back(&mut self, element: T) {
+        LinkedList::push_back(self, element)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        LinkedList::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        LinkedList::pop_back(self)
+    }
+
+    fn front(&self) -> Option<&T> {
+        LinkedList::front(self)
+    }
+
+    fn front_mut(&mut self) -> Option<&mut T> {
+        LinkedList::front_mut(self)
+    }
+
+    fn back(&self) -> Option<&T> {
+        LinkedList::back(self)
+    }
+
+    fn back_mut(&mut self) -> Option<&mut T> {
+        LinkedList::back_mut(self)
+    }
+
+    fn len(&self) -> usize {
+        LinkedList::len(self)
+    }
+}
+
+/// A list seen back to front: its front is the list's back and vice versa, so code written
+/// for one direction runs in the other.
+pub struct ReversedView<'a, T, A: Allocator = Global> {
+    list: &'a mut LinkedList<T, A>
+}
+
+impl<'a, T, A: Allocator> ReversedView<'a, T, A> {
+    pub fn push_front(&mut self, element: T) {
+        self.list.push_back(element)
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        self.list.push_front(element)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.list.back_mut()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.list.front_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> core::iter::Rev<Iter<'_, T>> {
+        self.list.iter().rev()
+    }
+
+    /// The underlying list, in its own orientation.
+    pub fn get_mut(&mut self) -> &mut LinkedList<T, A> {
+        self.list
+    }
+}
+
+impl<T, A: Allocator> Deque<T> for ReversedView<'_, T, A> {
+    fn push_front(&mut self, element: T) {
+        ReversedView::push_front(self, element)
+    }
+
+    fn push_back(&mut self, element: T) {
+        ReversedView::push_back(self, element)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        ReversedView::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        ReversedView::pop_back(self)
+    }
+
+    fn front(&self) -> Option<&T> {
+        ReversedView::front(self)
+    }
+
+    fn front_mut(&mut self) -> Option<&mut T> {
+        ReversedView::front_mut(self)
+    }
+
+    fn back(&self) -> Option<&T> {
+        ReversedView::back(self)
+    }
+
+    fn back_mut(&mut self) -> Option<&mut T> {
+        ReversedView::back_mut(self)
+    }
+
+    fn len(&self) -> usize {
+        ReversedView::len(self)
+    }
+}
+
+impl<'b, T, A: Allocator> IntoIterator for &'b ReversedView<'_, T, A> {
+    type Item = &'b T;
+    type IntoIter = core::iter::Rev<Iter<'b, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A read-only `ReversedView`, from `LinkedList::reversed_ref`.
+pub struct ReversedRef<'a, T, A: Allocator = Global> {
+    list: &'a LinkedList<T, A>
+}
+
+impl<T, A: Allocator> Clone for ReversedRef<'_, T, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, A: Allocator> Copy for ReversedRef<'_, T, A> {}
+
+impl<'a, T, A: Allocator> ReversedRef<'a, T, A> {
+    pub fn front(&self) -> Option<&'a T> {
+        self.list.back()
+    }
+
+    pub fn back(&self) -> Option<&'a T> {
+        self.list.front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> core::iter::Rev<Iter<'a, T>> {
+        self.list.iter().rev()
+    }
+
+    /// The underlying list, in its own orientation.
+    pub fn get(&self) -> &'a LinkedList<T, A> {
+        self.list
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for ReversedRef<'a, T, A> {
+    type Item = &'a T;
+    type IntoIter = core::iter::Rev<Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, A: Allocator> serde::Serialize for LinkedList<T, A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, A: Allocator + Default> serde::Deserialize<'de> for LinkedList<T, A> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_support::deserialize_seq(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, A: Allocator + Default> crate::serde_support::FromSeq<'de> for LinkedList<T, A> {
+    fn from_seq<S: serde::de::SeqAccess<'de>>(mut seq: S) -> Result<Self, S::Error> {
+        let mut list = LinkedList::new_in(A::default());
+        while let Some(element) = seq.next_element()? {
+            list.push_back(element);
+        }
+        Ok(list)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::LinkedList;
+    use crate::allocator::CountingAllocator;
+    use crate::invariants::InvariantError;
+    use super::{Deque, LinkedList};
 
     #[test]
     fn basics() {
         let mut list = LinkedList::new();
         list.push_front(3);
+        list.check_invariants().unwrap();
         list.push_front(6);
+        list.check_invariants().unwrap();
         list.push_front(2);
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_back(), Some(3));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_back(), Some(6));
+        list.check_invariants().unwrap();
         list.push_front(4);
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_back(), Some(2));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_back(), Some(4));
+        list.check_invariants().unwrap();
         assert_eq!(list.pop_back(), None);
+        list.check_invariants().unwrap();
     }
 
     #[test]
@@ -256,11 +758,17 @@ mod test {
         assert_eq!(list.back_mut(), None);
 
         list.push_front(3);
+        list.check_invariants().unwrap();
         list.push_front(2);
+        list.check_invariants().unwrap();
         list.push_back(4);
+        list.check_invariants().unwrap();
         list.push_back(5);
+        list.check_invariants().unwrap();
         list.push_front(1);
+        list.check_invariants().unwrap();
         list.push_back(6);
+        list.check_invariants().unwrap();
 
         assert_eq!(list.front(), Some(&1));
         assert_eq!(list.front_mut(), Some(&mut 1));
@@ -276,7 +784,9 @@ mod test {
         assert_eq!(list.back_mut(), Some(&mut -6));
 
         list.push_front(0);
+        list.check_invariants().unwrap();
         list.push_back(0);
+        list.check_invariants().unwrap();
 
         assert_eq!(list.front(), Some(&0));
         assert_eq!(list.back(), Some(&0));
@@ -289,36 +799,50 @@ mod test {
         // Try to break an empty list
         assert_eq!(list.len(), 0);
         assert_eq!(list.pop_front(), None);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
 
         // Try to break a one item list
         list.push_front(10);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 1);
         assert_eq!(list.pop_front(), Some(10));
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
         assert_eq!(list.pop_front(), None);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
 
         // Mess around
         list.push_front(10);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 1);
         list.push_front(20);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 2);
         list.push_front(30);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 3);
         assert_eq!(list.pop_front(), Some(30));
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 2);
         list.push_front(40);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 3);
         assert_eq!(list.pop_front(), Some(40));
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 2);
         assert_eq!(list.pop_front(), Some(20));
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 1);
         assert_eq!(list.pop_front(), Some(10));
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
         assert_eq!(list.pop_front(), None);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
         assert_eq!(list.pop_front(), None);
+        list.check_invariants().unwrap();
         assert_eq!(list.len(), 0);
     }
 
@@ -326,12 +850,19 @@ mod test {
     fn iter() {
         let mut list = LinkedList::new();
         list.push_back(1);
+        list.check_invariants().unwrap();
         list.push_back(2);
+        list.check_invariants().unwrap();
         list.push_back(3);
+        list.check_invariants().unwrap();
         list.push_back(4);
+        list.check_invariants().unwrap();
         list.push_back(5);
+        list.check_invariants().unwrap();
         list.push_back(6);
+        list.check_invariants().unwrap();
         list.push_back(7);
+        list.check_invariants().unwrap();
 
         let mut iter = list.iter();
         assert_eq!(iter.next(), Some(&1));
@@ -348,12 +879,19 @@ mod test {
     fn double_iter() {
         let mut list = LinkedList::new();
         list.push_back(1);
+        list.check_invariants().unwrap();
         list.push_back(2);
+        list.check_invariants().unwrap();
         list.push_back(3);
+        list.check_invariants().unwrap();
         list.push_back(4);
+        list.check_invariants().unwrap();
         list.push_back(5);
+        list.check_invariants().unwrap();
         list.push_back(6);
+        list.check_invariants().unwrap();
         list.push_back(7);
+        list.check_invariants().unwrap();
 
         let mut iter = list.iter();
         assert_eq!(iter.next(), Some(&1));
@@ -371,9 +909,13 @@ mod test {
     fn into_iter() {
         let mut list = LinkedList::new();
         list.push_back(5);
+        list.check_invariants().unwrap();
         list.push_back(10);
+        list.check_invariants().unwrap();
         list.push_back(3);
+        list.check_invariants().unwrap();
         list.push_back(8);
+        list.check_invariants().unwrap();
 
         let mut iter = list.into_iter();
         assert_eq!(iter.next(), Some(5));
@@ -382,4 +924,245 @@ mod test {
         assert_eq!(iter.next_back(), Some(3));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn node_cache() {
+        let mut list = LinkedList::with_node_cache(2);
+        assert_eq!(list.node_cache_len(), 0);
+
+        list.push_back(String::from("a"));
+        list.check_invariants().unwrap();
+        list.push_back(String::from("b"));
+        list.check_invariants().unwrap();
+        list.push_back(String::from("c"));
+        list.check_invariants().unwrap();
+
+        // Only as many nodes as the cache can hold are kept around
+        assert_eq!(list.pop_front(), Some(String::from("a")));
+        list.check_invariants().unwrap();
+        assert_eq!(list.pop_back(), Some(String::from("c")));
+        list.check_invariants().unwrap();
+        assert_eq!(list.pop_front(), Some(String::from("b")));
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 2);
+
+        // Pushes reuse cached nodes before allocating new ones
+        list.push_front(String::from("d"));
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 1);
+        list.push_back(String::from("e"));
+        list.check_invariants().unwrap();
+        list.push_back(String::from("f"));
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 0);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(), vec!["d", "e", "f"]);
+
+        list.pop_back();
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 1);
+        list.shrink_node_cache();
+        assert_eq!(list.node_cache_len(), 0);
+        assert_eq!(list.len(), 2);
+
+        // The cache fills up again after being shrunk
+        list.pop_back();
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 1);
+        assert_eq!(list.front(), Some(&String::from("d")));
+    }
+
+    #[test]
+    fn custom_allocator() {
+        let allocator = CountingAllocator::new();
+        {
+            let mut list = LinkedList::new_in(&allocator);
+            list.push_back(String::from("a"));
+            list.check_invariants().unwrap();
+            list.push_front(String::from("b"));
+            list.check_invariants().unwrap();
+            list.push_back(String::from("c"));
+            list.check_invariants().unwrap();
+            assert_eq!(allocator.allocations(), 3);
+
+            assert_eq!(list.pop_back(), Some(String::from("c")));
+            list.check_invariants().unwrap();
+            assert_eq!(list.pop_front(), Some(String::from("b")));
+            list.check_invariants().unwrap();
+            assert_eq!(allocator.deallocations(), 2);
+            assert_eq!(allocator.live(), 1);
+
+            list.push_back(String::from("d"));
+            list.check_invariants().unwrap();
+        }
+        // Dropping the list returns the rest of its nodes to the same allocator
+        assert_eq!(allocator.allocations(), 4);
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[test]
+    fn custom_allocator_with_node_cache() {
+        let allocator = CountingAllocator::new();
+        {
+            let mut list = LinkedList::with_node_cache_in(4, &allocator);
+            for round in 0..10 {
+                for i in 0..4 {
+                    list.push_back(round * 4 + i);
+                    list.check_invariants().unwrap();
+                }
+                while list.pop_front().is_some() {}
+            }
+            assert_eq!(allocator.allocations(), 4);
+            assert_eq!(allocator.live(), 4);
+
+            list.shrink_node_cache();
+            assert_eq!(allocator.live(), 0);
+
+            list.push_back(1);
+            list.check_invariants().unwrap();
+            list.pop_back();
+            list.check_invariants().unwrap();
+        }
+        assert_eq!(allocator.allocations(), 5);
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[test]
+    fn no_node_cache_by_default() {
+        let mut list = LinkedList::new();
+        list.push_back(1);
+        list.check_invariants().unwrap();
+        list.pop_back();
+        list.check_invariants().unwrap();
+        assert_eq!(list.node_cache_len(), 0);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list = LinkedList::new();
+        list.reverse();
+        list.check_invariants().unwrap();
+        for length in 1..6 {
+            list.push_back(length);
+            list.reverse();
+            list.check_invariants().unwrap();
+        }
+        assert!(list.iter().eq([5, 3, 1, 2, 4].iter()));
+        assert!(list.iter().rev().eq([4, 2, 1, 3, 5].iter()));
+
+        list.push_front(0);
+        list.check_invariants().unwrap();
+        assert_eq!(list.pop_back(), Some(4));
+        list.check_invariants().unwrap();
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    fn reversed_view() {
+        // Written for the front only; the view lets it work on the back
+        fn take_while_small(front: &mut impl Deque<i32>) -> Vec<i32> {
+            let mut taken = Vec::new();
+            while front.front().is_some_and(|&element| element < 10) {
+                taken.push(front.pop_front().unwrap());
+            }
+            front.push_front(0);
+            taken
+        }
+
+        let mut list = LinkedList::new();
+        for element in [20, 30, 3, 2, 1] {
+            list.push_back(element);
+        }
+        let mut view = list.reversed();
+        assert_eq!((view.front(), view.back(), view.len()), (Some(&1), Some(&20), 5));
+        assert_eq!(take_while_small(&mut view), vec![1, 2, 3]);
+        view.push_back(10);
+        *view.back_mut().unwrap() += 1;
+        assert!(view.iter().eq([0, 30, 20, 11].iter()));
+        assert!((&view).into_iter().eq([0, 30, 20, 11].iter()));
+        view.get_mut().check_invariants().unwrap();
+        assert!(list.iter().eq([11, 20, 30, 0].iter()));
+
+        // The same code runs forwards on the list itself
+        assert_eq!(take_while_small(&mut list), vec![]);
+        assert_eq!(list.front(), Some(&0));
+
+        let view = list.reversed_ref();
+        assert_eq!((view.front(), view.back(), view.len()), (Some(&0), Some(&0), 5));
+        assert!(view.into_iter().eq([0, 30, 20, 11, 0].iter()));
+        assert!(view.iter().rev().eq(list.iter()));
+    }
+
+    #[test]
+    fn broken_invariants() {
+        let mut list = LinkedList::new();
+        for i in 0..3 {
+            list.push_back(i);
+            list.check_invariants().unwrap();
+        }
+        let (a, c) = (list.head.unwrap().as_ptr(), list.tail.unwrap().as_ptr());
+
+        // Each corruption is undone before the next, so the list still drops cleanly
+        unsafe {
+            let b = (*a).next.unwrap().as_ptr();
+
+            list.length = 4;
+            assert_eq!(list.check_invariants(), Err(InvariantError::LengthMismatch { length: 4, counted: 3 }));
+            list.length = 3;
+
+            (*b).previous = None;
+            assert_eq!(list.check_invariants(), Err(InvariantError::BrokenBackLink { index: 1 }));
+            (*b).previous = list.tail;
+            assert_eq!(list.check_invariants(), Err(InvariantError::BrokenBackLink { index: 1 }));
+            (*b).previous = list.head;
+
+            (*b).next = list.head;
+            assert_eq!(list.check_invariants(), Err(InvariantError::Cycle { index: 1 }));
+            (*b).next = list.tail;
+
+            (*a).next = None;
+            assert_eq!(list.check_invariants(), Err(InvariantError::TailUnreachable));
+            (*a).next = (*c).previous;
+
+            (*a).previous = list.tail;
+            assert_eq!(list.check_invariants(), Err(InvariantError::HeadHasPrevious));
+            (*a).previous = None;
+
+            (*c).next = list.head;
+            assert_eq!(list.check_invariants(), Err(InvariantError::TailHasNext));
+            (*c).next = None;
+        }
+
+        let tail = list.tail.take();
+        assert_eq!(list.check_invariants(), Err(InvariantError::MismatchedEnds));
+        assert_eq!(list.check_invariants().unwrap_err().to_string(), "only one of head and tail is set");
+        list.tail = tail;
+        list.check_invariants().unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::serde_support::json;
+
+        let mut list = LinkedList::new();
+        let mut inner = LinkedList::new();
+        inner.push_back(1);
+        inner.check_invariants().unwrap();
+        inner.push_back(2);
+        inner.check_invariants().unwrap();
+        list.push_back(inner);
+        list.check_invariants().unwrap();
+        list.push_front(LinkedList::new());
+        list.check_invariants().unwrap();
+        let text = json::to_string(&list).unwrap();
+        assert_eq!(text, "[[],[1,2]]");
+
+        let decoded: LinkedList<LinkedList<i32>> = json::from_str(" [ [ ] , [1 ,2] ] ").unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded.iter().flatten().eq([1, 2].iter()));
+        assert_eq!(decoded.back().unwrap().back(), Some(&2));
+
+        let error = json::from_str::<LinkedList<i32>>("[1,").err().unwrap();
+        assert_eq!(error.0, "unexpected end of input");
+    }
 }
@@ -0,0 +1,584 @@
+//! An ordered map on a skip list: every node sits on the level-0 list like a `sixth` node,
+//! and some also sit on sparser express lanes above it, which searches use to skip ahead.
+//!
+//! Node heights come from a seeded generator rather than the OS, so the same sequence of
+//! operations on the same seed always builds the same towers.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Bound, RangeBounds};
+use core::ptr::NonNull;
+
+const MAX_LEVEL: usize = 32;
+const DEFAULT_SEED: u64 = 0x5eed_1157_0fc0_ffee;
+
+pub struct SkipListMap<K, V> {
+    // Forward pointers of the sentinel tower in front of the first node
+    head: [Link<K, V>; MAX_LEVEL],
+    tail: Link<K, V>,
+    // Number of levels any node currently reaches
+    level: usize,
+    length: usize,
+    rng: u64,
+    _boo: PhantomData<Box<Node<K, V>>>
+}
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    previous: Link<K, V>,
+    next: Box<[Link<K, V>]>
+}
+
+unsafe impl<K: Send, V: Send> Send for SkipListMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipListMap<K, V> {}
+
+impl<K, V> SkipListMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates an empty map whose node heights are drawn from a generator seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        SkipListMap {
+            head: [None; MAX_LEVEL],
+            tail: None,
+            level: 0,
+            length: 0,
+            rng: seed,
+            _boo: PhantomData
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.head[0].map(|node| unsafe { Self::entry(node) })
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.tail.map(|node| unsafe { Self::entry(node) })
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.head[0].map(|node| unsafe {
+            // The first node is first on every level it reaches, so the sentinel precedes it
+            self.unlink(&[None; MAX_LEVEL], node)
+        })
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> where K: Ord {
+        self.tail.map(|node| unsafe {
+            let update = self.predecessors(&(*node.as_ptr()).key);
+            self.unlink(&update, node)
+        })
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { front: self.head[0], back: self.tail, length: self.length, _boo: PhantomData }
+    }
+
+    unsafe fn entry<'a>(node: NonNull<Node<K, V>>) -> (&'a K, &'a V) {
+        let node = &*node.as_ptr();
+        (&node.key, &node.value)
+    }
+
+    // The successor of `at` on `level`, where `None` stands for the sentinel
+    unsafe fn forward(&self, at: Link<K, V>, level: usize) -> Link<K, V> {
+        match at {
+            Some(node) => (*node.as_ptr()).next[level],
+            None => self.head[level]
+        }
+    }
+
+    unsafe fn set_forward(&mut self, at: Link<K, V>, level: usize, to: Link<K, V>) {
+        match at {
+            Some(node) => (*node.as_ptr()).next[level] = to,
+            None => self.head[level] = to
+        }
+    }
+
+    // The last node whose key satisfies `before`, which must hold for a prefix of the keys
+    fn last_where(&self, before: impl Fn(&K) -> bool) -> Link<K, V> {
+        let mut current = None;
+        for level in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = self.forward(current, level) {
+                    if !before(&(*next.as_ptr()).key) {
+                        break;
+                    }
+                    current = Some(next);
+                }
+            }
+        }
+        current
+    }
+
+    // On each level, the last node with a key below `key`
+    fn predecessors<Q>(&self, key: &Q) -> [Link<K, V>; MAX_LEVEL]
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        let mut update = [None; MAX_LEVEL];
+        let mut current = None;
+        for level in (0..self.level).rev() {
+            unsafe {
+                while let Some(next) = self.forward(current, level) {
+                    if (*next.as_ptr()).key.borrow() >= key {
+                        break;
+                    }
+                    current = Some(next);
+                }
+            }
+            update[level] = current;
+        }
+        update
+    }
+
+    fn find<Q>(&self, key: &Q) -> Link<K, V>
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        let candidate = unsafe { self.forward(self.last_where(|k| k.borrow() < key), 0) };
+        candidate.filter(|node| unsafe { (*node.as_ptr()).key.borrow() == key })
+    }
+
+    unsafe fn unlink(&mut self, update: &[Link<K, V>; MAX_LEVEL], node: NonNull<Node<K, V>>) -> (K, V) {
+        let boxed = Box::from_raw(node.as_ptr());
+        for (level, &next) in boxed.next.iter().enumerate() {
+            self.set_forward(update[level], level, next);
+        }
+        match boxed.next[0] {
+            Some(next) => (*next.as_ptr()).previous = boxed.previous,
+            None => self.tail = boxed.previous
+        }
+        while self.level > 0 && self.head[self.level - 1].is_none() {
+            self.level -= 1;
+        }
+        self.length -= 1;
+        (boxed.key, boxed.value)
+    }
+
+    // Each level up is half as likely as the one below it
+    fn random_level(&mut self) -> usize {
+        // splitmix64, which copes with any seed including zero
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z.trailing_ones() as usize + 1).min(MAX_LEVEL)
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    /// Inserts `value` under `key`, returning the value it replaced if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let update = self.predecessors(&key);
+        unsafe {
+            if let Some(existing) = self.forward(update[0], 0) {
+                if (*existing.as_ptr()).key == key {
+                    return Some(mem::replace(&mut (*existing.as_ptr()).value, value));
+                }
+            }
+
+            let height = self.random_level();
+            // Levels above the old top have the sentinel as predecessor, which `update` holds as `None`
+            self.level = self.level.max(height);
+
+            let node = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                key,
+                value,
+                previous: update[0],
+                next: vec![None; height].into_boxed_slice()
+            })));
+            for (level, &predecessor) in update.iter().enumerate().take(height) {
+                (*node.as_ptr()).next[level] = self.forward(predecessor, level);
+                self.set_forward(predecessor, level, Some(node));
+            }
+            match (*node.as_ptr()).next[0] {
+                Some(next) => (*next.as_ptr()).previous = Some(node),
+                None => self.tail = Some(node)
+            }
+        }
+        self.length += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.find(key).map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.find(key).map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where K: Borrow<Q>, Q: Ord + ?Sized {
+        let update = self.predecessors(key);
+        unsafe {
+            let node = self.forward(update[0], 0)
+                .filter(|node| (*node.as_ptr()).key.borrow() == key)?;
+            Some(self.unlink(&update, node).1)
+        }
+    }
+
+    /// Iterates over the entries whose keys fall in `range`, in key order.
+    ///
+    /// Unlike `BTreeMap::range`, a range whose start lies after its end is simply empty.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where K: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q> {
+        let front = match range.start_bound() {
+            Bound::Included(start) => unsafe { self.forward(self.last_where(|k| k.borrow() < start), 0) },
+            Bound::Excluded(start) => unsafe { self.forward(self.last_where(|k| k.borrow() <= start), 0) },
+            Bound::Unbounded => self.head[0]
+        };
+        let back = match range.end_bound() {
+            Bound::Included(end) => self.last_where(|k| k.borrow() <= end),
+            Bound::Excluded(end) => self.last_where(|k| k.borrow() < end),
+            Bound::Unbounded => self.tail
+        };
+        let empty = match (front, back) {
+            (Some(front), Some(back)) => unsafe { (*front.as_ptr()).key > (*back.as_ptr()).key },
+            _ => true
+        };
+        if empty {
+            Range { front: None, back: None, _boo: PhantomData }
+        } else {
+            Range { front, back, _boo: PhantomData }
+        }
+    }
+}
+
+impl<K, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for SkipListMap<K, V> {
+    fn drop(&mut self) {
+        let mut current = self.head[0];
+        while let Some(node) = current {
+            unsafe {
+                let boxed = Box::from_raw(node.as_ptr());
+                current = boxed.next[0];
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipListMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SkipListMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = SkipListMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipListMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    front: Link<K, V>,
+    back: Link<K, V>,
+    length: usize,
+    _boo: PhantomData<(&'a K, &'a V)>
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.front.map(|node| unsafe {
+                self.length -= 1;
+                self.front = (*node.as_ptr()).next[0];
+                SkipListMap::entry(node)
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.back.map(|node| unsafe {
+                self.length -= 1;
+                self.back = (*node.as_ptr()).previous;
+                SkipListMap::entry(node)
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {
+    fn len(&self) -> usize {
+        self.length
+    }
+}
+
+pub struct Range<'a, K, V> {
+    // Both ends are `None` once the iterator is exhausted
+    front: Link<K, V>,
+    back: Link<K, V>,
+    _boo: PhantomData<(&'a K, &'a V)>
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.front.map(|node| unsafe {
+            if self.front == self.back {
+                self.front = None;
+                self.back = None;
+            } else {
+                self.front = (*node.as_ptr()).next[0];
+            }
+            SkipListMap::entry(node)
+        })
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.map(|node| unsafe {
+            if self.front == self.back {
+                self.front = None;
+                self.back = None;
+            } else {
+                self.back = (*node.as_ptr()).previous;
+            }
+            SkipListMap::entry(node)
+        })
+    }
+}
+
+pub struct IntoIter<K, V> {
+    map: SkipListMap<K, V>
+}
+
+impl<K, V> IntoIterator for SkipListMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { map: self }
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.map.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.map.length, Some(self.map.length))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+    use std::rc::Rc;
+    use super::SkipListMap;
+
+    // Tower height of every node, in key order
+    fn heights<K, V>(map: &SkipListMap<K, V>) -> Vec<usize> {
+        let mut heights = Vec::new();
+        let mut current = map.head[0];
+        while let Some(node) = current {
+            unsafe {
+                heights.push(node.as_ref().next.len());
+                current = (*node.as_ptr()).next[0];
+            }
+        }
+        heights
+    }
+
+    #[test]
+    fn basics() {
+        let mut map = SkipListMap::new();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&1), None);
+
+        assert_eq!(map.insert(3, "three"), None);
+        assert_eq!(map.insert(1, "one"), None);
+        assert_eq!(map.insert(2, "two"), None);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&"two"));
+
+        // Check replacing an existing key keeps the length
+        assert_eq!(map.insert(2, "deux"), Some("two"));
+        assert_eq!(map.len(), 3);
+        *map.get_mut(&3).unwrap() = "trois";
+
+        assert_eq!(map.remove(&1), Some("one"));
+        assert_eq!(map.remove(&1), None);
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&2, &"deux"), (&3, &"trois")]);
+    }
+
+    #[test]
+    fn first_last() {
+        let mut map: SkipListMap<_, _> = [(5, 'e'), (1, 'a'), (3, 'c')].into_iter().collect();
+        assert_eq!(map.first(), Some((&1, &'a')));
+        assert_eq!(map.last(), Some((&5, &'e')));
+
+        assert_eq!(map.pop_last(), Some((5, 'e')));
+        assert_eq!(map.pop_first(), Some((1, 'a')));
+        assert_eq!(map.first(), map.last());
+        assert_eq!(map.pop_last(), Some((3, 'c')));
+        assert_eq!(map.first(), None);
+        assert_eq!(map.last(), None);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = SkipListMap::new();
+        map.insert(String::from("b"), 2);
+        map.insert(String::from("a"), 1);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.range::<str, _>((Bound::Included("b"), Bound::Unbounded)).count(), 1);
+        assert_eq!(map.remove("b"), Some(2));
+    }
+
+    #[test]
+    fn range() {
+        let map: SkipListMap<i32, i32> = (0..20).map(|i| (i * 2, i)).collect();
+        let keys = |range: super::Range<'_, i32, i32>| range.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(4..10)), vec![4, 6, 8]);
+        assert_eq!(keys(map.range(3..=10)), vec![4, 6, 8, 10]);
+        assert_eq!(keys(map.range(35..)), vec![36, 38]);
+        assert_eq!(keys(map.range(..3)), vec![0, 2]);
+        assert_eq!(keys(map.range((Bound::Excluded(4), Bound::Excluded(10)))), vec![6, 8]);
+        assert_eq!(map.range(..).count(), 20);
+
+        // Check ranges that match nothing
+        assert_eq!(map.range(5..6).next(), None);
+        assert_eq!(map.range(50..).next(), None);
+        assert_eq!(map.range((Bound::Included(10), Bound::Excluded(4))).next(), None);
+
+        let mut range = map.range(2..=8);
+        assert_eq!(range.next(), Some((&2, &1)));
+        assert_eq!(range.next_back(), Some((&8, &4)));
+        assert_eq!(range.next_back(), Some((&6, &3)));
+        assert_eq!(range.next(), Some((&4, &2)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let map: SkipListMap<i32, ()> = [4, 2, 3, 1].into_iter().map(|k| (k, ())).collect();
+        let mut iter = map.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next().map(|(k, _)| *k), Some(1));
+        assert_eq!(iter.next_back().map(|(k, _)| *k), Some(4));
+        assert_eq!(iter.next().map(|(k, _)| *k), Some(2));
+        assert_eq!(iter.next_back().map(|(k, _)| *k), Some(3));
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(map.into_iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn seeded_levels_are_reproducible() {
+        let build = |seed| {
+            let mut map = SkipListMap::with_seed(seed);
+            for i in 0..200 {
+                map.insert((i * 37) % 200, i);
+            }
+            heights(&map)
+        };
+        assert_eq!(build(7), build(7));
+        assert_ne!(build(7), build(8));
+        assert!(build(7).iter().any(|&height| height > 1));
+    }
+
+    #[test]
+    fn matches_btreemap() {
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let mut map = SkipListMap::with_seed(42);
+        let mut model = BTreeMap::new();
+        for step in 0..5_000 {
+            let key = next() % 500;
+            match next() % 3 {
+                0 | 1 => assert_eq!(map.insert(key, step), model.insert(key, step)),
+                _ => assert_eq!(map.remove(&key), model.remove(&key))
+            }
+            assert_eq!(map.len(), model.len());
+        }
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.iter().rev().eq(model.iter().rev()));
+        assert!(map.range(100..300).eq(model.range(100..300)));
+        assert_eq!(map.first(), model.first_key_value());
+        assert_eq!(map.last(), model.last_key_value());
+    }
+
+    #[test]
+    fn drops_every_entry() {
+        let tracker = Rc::new(());
+        {
+            let mut map = SkipListMap::new();
+            for i in 0..50 {
+                map.insert(i, tracker.clone());
+            }
+            map.remove(&10);
+            map.insert(3, tracker.clone());
+            assert_eq!(Rc::strong_count(&tracker), 50);
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+}
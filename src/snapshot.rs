@@ -0,0 +1,293 @@
+//! A dependency-free binary snapshot of a `sixth::LinkedList`, for crash-recovery files.
+//!
+//! The layout is a header of the magic bytes `LLSS` and a little-endian `u16` format version,
+//! then the element count as a little-endian `u64`, then each element in order. Integers are
+//! fixed-width little-endian; strings and byte vectors are a `u64` length followed by the bytes.
+//!
+//! Decoding checks every length against the bytes actually left, the element count included,
+//! so truncated or corrupt input comes back as a `DecodeError` instead of a panic, a huge
+//! allocation or a runaway loop.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use crate::allocator::Allocator;
+use crate::sixth::LinkedList;
+
+const MAGIC: [u8; 4] = *b"LLSS";
+pub const VERSION: u16 = 1;
+
+/// A value that can be written into a snapshot.
+pub trait Encode {
+    fn encode(&self, output: &mut Vec<u8>);
+}
+
+/// A value that can be read back out of a snapshot. Every element takes at least one byte,
+/// which is what lets `decode` reject element counts the input can't hold.
+pub trait Decode: Sized {
+    fn decode(input: &mut Decoder<'_>) -> Result<Self, DecodeError>;
+}
+
+/// Encodes `list` front to back.
+pub fn encode<T: Encode, A: Allocator>(list: &LinkedList<T, A>) -> Vec<u8> {
+    let mut output = Vec::new();
+    output.extend_from_slice(&MAGIC);
+    VERSION.encode(&mut output);
+    (list.len() as u64).encode(&mut output);
+    for element in list {
+        element.encode(&mut output);
+    }
+    output
+}
+
+/// Decodes a list written by `encode`, rejecting anything left over after the last element.
+pub fn decode<T: Decode>(bytes: &[u8]) -> Result<LinkedList<T>, DecodeError> {
+    let mut input = Decoder { bytes, offset: 0 };
+    if input.take(MAGIC.len())? != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = u16::decode(&mut input)?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    // Each element takes at least a byte, so a count bigger than what's left can't be right
+    let count = input.length()?;
+    let mut list = LinkedList::new();
+    for _ in 0..count {
+        list.push_back(T::decode(&mut input)?);
+    }
+
+    if input.remaining() != 0 {
+        return Err(DecodeError::TrailingBytes { offset: input.offset, remaining: input.remaining() });
+    }
+    Ok(list)
+}
+
+/// A cursor over the snapshot being decoded, which `Decode` impls read from.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Decoder<'a> {
+    /// Takes the next `count` bytes, or fails without consuming anything if there aren't enough.
+    pub fn take(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        if count > self.remaining() {
+            return Err(DecodeError::UnexpectedEof { offset: self.offset, needed: count });
+        }
+        let taken = &self.bytes[self.offset..self.offset + count];
+        self.offset += count;
+        Ok(taken)
+    }
+
+    /// Reads a `u64` length prefix and checks that that many bytes are actually left.
+    pub fn length(&mut self) -> Result<usize, DecodeError> {
+        let offset = self.offset;
+        let length = u64::decode(self)?;
+        match usize::try_from(length) {
+            Ok(length) if length <= self.remaining() => Ok(length),
+            _ => Err(DecodeError::BadLength { offset, length })
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+}
+
+macro_rules! impl_integers {
+    ($($integer:ty),*) => {
+        $(
+            impl Encode for $integer {
+                fn encode(&self, output: &mut Vec<u8>) {
+                    output.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $integer {
+                fn decode(input: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let bytes = input.take(core::mem::size_of::<$integer>())?;
+                    Ok(<$integer>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_integers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Encode for [u8] {
+    fn encode(&self, output: &mut Vec<u8>) {
+        (self.len() as u64).encode(output);
+        output.extend_from_slice(self);
+    }
+}
+
+impl Encode for Vec<u8> {
+    fn encode(&self, output: &mut Vec<u8>) {
+        self.as_slice().encode(output);
+    }
+}
+
+impl Decode for Vec<u8> {
+    fn decode(input: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let length = input.length()?;
+        Ok(input.take(length)?.to_vec())
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, output: &mut Vec<u8>) {
+        self.as_bytes().encode(output);
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, output: &mut Vec<u8>) {
+        self.as_str().encode(output);
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let length = input.length()?;
+        let offset = input.offset();
+        String::from_utf8(input.take(length)?.to_vec())
+            .map_err(|error| DecodeError::InvalidUtf8 { offset: offset + error.utf8_error().valid_up_to() })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input doesn't start with the snapshot magic bytes.
+    BadMagic,
+    /// The snapshot was written by a format version this code can't read.
+    UnsupportedVersion(u16),
+    /// The input ended at `offset` while `needed` more bytes were expected.
+    UnexpectedEof { offset: usize, needed: usize },
+    /// The length prefix at `offset` is longer than the input left after it.
+    BadLength { offset: usize, length: u64 },
+    /// A string's bytes stop being valid UTF-8 at `offset`.
+    InvalidUtf8 { offset: usize },
+    /// Every element decoded, but `remaining` bytes were left over from `offset`.
+    TrailingBytes { offset: usize, remaining: usize }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => f.write_str("not a list snapshot"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {} (expected {})", version, VERSION)
+            }
+            DecodeError::UnexpectedEof { offset, needed } => {
+                write!(f, "snapshot truncated at byte {}: needed {} more bytes", offset, needed)
+            }
+            DecodeError::BadLength { offset, length } => {
+                write!(f, "length {} at byte {} runs past the end of the snapshot", length, offset)
+            }
+            DecodeError::InvalidUtf8 { offset } => write!(f, "invalid UTF-8 at byte {}", offset),
+            DecodeError::TrailingBytes { offset, remaining } => {
+                write!(f, "{} unexpected bytes after the last element at byte {}", remaining, offset)
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+#[cfg(test)]
+mod test {
+    use crate::sixth::LinkedList;
+    use super::{decode, encode, DecodeError, VERSION};
+
+    fn error_of(bytes: &[u8]) -> DecodeError {
+        decode::<String>(bytes).err().expect("corrupt snapshot decoded")
+    }
+
+    fn list_of<T>(elements: impl IntoIterator<Item = T>) -> LinkedList<T> {
+        let mut list = LinkedList::new();
+        for element in elements {
+            list.push_back(element);
+        }
+        list
+    }
+
+    #[test]
+    fn header_layout() {
+        let bytes = encode(&list_of([0x0102_u16, 0x0304]));
+        assert_eq!(&bytes[..4], b"LLSS");
+        assert_eq!(&bytes[4..6], &VERSION.to_le_bytes());
+        assert_eq!(&bytes[6..14], &2_u64.to_le_bytes());
+        assert_eq!(&bytes[14..], &[0x02, 0x01, 0x04, 0x03]);
+    }
+
+    #[test]
+    fn round_trips() {
+        let integers = list_of([i64::MIN, -1, 0, 1, i64::MAX]);
+        let decoded: LinkedList<i64> = decode(&encode(&integers)).unwrap();
+        assert!(decoded.iter().eq(integers.iter()));
+
+        let strings = list_of(["", "héllo", "snapshot"].map(String::from));
+        let decoded: LinkedList<String> = decode(&encode(&strings)).unwrap();
+        assert!(decoded.iter().eq(strings.iter()));
+
+        let bytes = list_of([vec![], vec![0, 255, 7]]);
+        let decoded: LinkedList<Vec<u8>> = decode(&encode(&bytes)).unwrap();
+        assert!(decoded.iter().eq(bytes.iter()));
+
+        let empty: LinkedList<u8> = decode(&encode(&LinkedList::<u8>::new())).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn every_truncation_is_an_error() {
+        let bytes = encode(&list_of(["one", "two", "three"].map(String::from)));
+        for end in 0..bytes.len() {
+            assert!(decode::<String>(&bytes[..end]).is_err(), "prefix of {} bytes decoded", end);
+        }
+        // A short payload is caught at its length prefix, a short prefix when reading it
+        assert_eq!(error_of(&bytes[..bytes.len() - 1]), DecodeError::BadLength { offset: bytes.len() - 13, length: 5 });
+        assert_eq!(error_of(&bytes[..bytes.len() - 6]), DecodeError::UnexpectedEof { offset: bytes.len() - 13, needed: 8 });
+    }
+
+    #[test]
+    fn corrupt_input() {
+        let mut bytes = encode(&list_of([String::from("ab")]));
+        assert_eq!(error_of(b"XXXX"), DecodeError::BadMagic);
+
+        let mut future = bytes.clone();
+        future[4] = 9;
+        assert_eq!(error_of(&future), DecodeError::UnsupportedVersion(9));
+
+        // A huge length prefix is rejected before anything is allocated for it
+        let mut huge = bytes.clone();
+        huge[14..22].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error_of(&huge), DecodeError::BadLength { offset: 14, length: u64::MAX });
+
+        let mut invalid = bytes.clone();
+        invalid[23] = 0xff;
+        assert_eq!(error_of(&invalid), DecodeError::InvalidUtf8 { offset: 23 });
+
+        // More elements claimed than present, and bytes left over after the last one
+        let mut overcount = bytes.clone();
+        overcount[6] = 2;
+        assert!(matches!(error_of(&overcount), DecodeError::UnexpectedEof { .. }));
+        overcount[6..14].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error_of(&overcount), DecodeError::BadLength { offset: 6, length: u64::MAX });
+        overcount[6..14].copy_from_slice(&11_u64.to_le_bytes());
+        assert_eq!(error_of(&overcount), DecodeError::BadLength { offset: 6, length: 11 });
+        bytes.push(0);
+        assert_eq!(error_of(&bytes), DecodeError::TrailingBytes { offset: 24, remaining: 1 });
+        assert_eq!(
+            error_of(&bytes).to_string(),
+            "1 unexpected bytes after the last element at byte 24"
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! A `sixth::LinkedList` kept in ascending order, so sorted event lists don't need a full sort
+//! after every insert.
+//!
+//! Equal elements keep their insertion order: a new element goes after any equal ones already
+//! in the list, and `merge` places the other list's elements after equal ones from this list.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+use core::ptr::NonNull;
+use crate::sixth::{self, LinkedList, Node};
+
+pub struct SortedList<T> {
+    list: LinkedList<T>
+}
+
+impl<T: Ord> SortedList<T> {
+    pub fn new() -> Self {
+        SortedList { list: LinkedList::new() }
+    }
+
+    /// Inserts `element` in order, walking in from both ends at once so the search costs
+    /// as many steps as the distance to the nearer end.
+    pub fn insert(&mut self, element: T) {
+        let mut forward = self.list.head_node();
+        let mut backward = self.list.tail_node();
+        // The node the new element goes in front of, `None` meaning the back
+        let next = unsafe {
+            loop {
+                match forward {
+                    Some(node) if Node::element(node) > &element => break Some(node),
+                    Some(node) => forward = Node::next(node),
+                    None => break None
+                }
+                match backward {
+                    Some(node) if Node::element(node) <= &element => break Node::next(node),
+                    Some(node) => backward = Node::previous(node),
+                    None => break self.list.head_node()
+                }
+            }
+        };
+        unsafe { self.list.insert_before(next, element); }
+    }
+
+    /// Moves every element of `other` into this list in O(n + m) by relinking its nodes,
+    /// leaving `other` empty.
+    pub fn merge(&mut self, other: &mut SortedList<T>) {
+        let mut mine = self.list.head_node();
+        let mut theirs = other.list.detach_all();
+        unsafe {
+            while let Some(first) = theirs {
+                let Some(anchor) = mine else {
+                    // Everything left in `other` sorts after this list's tail
+                    let mut last = first;
+                    let mut count = 1;
+                    while let Some(next) = Node::next(last) {
+                        last = next;
+                        count += 1;
+                    }
+                    self.list.splice_before(None, first, last, count);
+                    break;
+                };
+
+                let bound = Node::element(anchor);
+                if Node::element(first) >= bound {
+                    mine = Node::next(anchor);
+                    continue;
+                }
+                // Splice in the whole run of `other` that sorts strictly before `anchor`
+                let mut last = first;
+                let mut count = 1;
+                theirs = Node::next(first);
+                while let Some(next) = theirs.filter(|&next| Node::element(next) < bound) {
+                    last = next;
+                    count += 1;
+                    theirs = Node::next(next);
+                }
+                self.list.splice_before(Some(anchor), first, last, count);
+            }
+        }
+    }
+
+    /// Removes one element equal to `value`, if there is one.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        self.find(value).map(|node| unsafe { self.list.unlink_node(node) })
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Iterates in order over the elements that fall in `range`.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> sixth::Iter<'_, T> {
+        let below_start = |element: &T| match range.start_bound() {
+            Bound::Included(start) => element < start,
+            Bound::Excluded(start) => element <= start,
+            Bound::Unbounded => false
+        };
+        let above_end = |element: &T| match range.end_bound() {
+            Bound::Included(end) => element > end,
+            Bound::Excluded(end) => element >= end,
+            Bound::Unbounded => false
+        };
+
+        unsafe {
+            let mut skipped = 0;
+            let mut front = self.list.head_node();
+            while let Some(node) = front.filter(|&node| below_start(Node::element(node))) {
+                front = Node::next(node);
+                skipped += 1;
+            }
+            let mut back = self.list.tail_node();
+            while let Some(node) = back.filter(|&node| above_end(Node::element(node))) {
+                back = Node::previous(node);
+                skipped += 1;
+            }
+            // A range whose start lies after its end has both walks cross and overcount
+            let length = self.list.len().saturating_sub(skipped);
+            sixth::Iter::new(front, back, length)
+        }
+    }
+
+    // The first node equal to `value`, stopping as soon as the elements pass it
+    fn find(&self, value: &T) -> Option<NonNull<Node<T>>> {
+        let mut current = self.list.head_node();
+        unsafe {
+            while let Some(node) = current {
+                match Node::element(node).cmp(value) {
+                    Ordering::Less => current = Node::next(node),
+                    Ordering::Equal => return Some(node),
+                    Ordering::Greater => return None
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T> SortedList<T> {
+    pub fn first(&self) -> Option<&T> {
+        self.list.front()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.list.back()
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.list.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn iter(&self) -> sixth::Iter<'_, T> {
+        self.list.iter()
+    }
+
+    #[cfg(test)]
+    fn inner(&self) -> &LinkedList<T> {
+        &self.list
+    }
+
+    /// Gives back the underlying list, still in ascending order.
+    pub fn into_list(self) -> LinkedList<T> {
+        self.list
+    }
+}
+
+impl<T: Ord> Default for SortedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for SortedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = SortedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedList<T> {
+    type Item = &'a T;
+    type IntoIter = sixth::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for SortedList<T> {
+    type Item = T;
+    type IntoIter = sixth::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+    use super::SortedList;
+
+    // Orders by `key` only, so equal elements can be told apart by `tag`
+    #[derive(Debug, PartialEq, Eq)]
+    struct Event {
+        key: u32,
+        tag: char
+    }
+
+    impl PartialOrd for Event {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Event {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    fn event(key: u32, tag: char) -> Event {
+        Event { key, tag }
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = SortedList::new();
+        assert_eq!(list.first(), None);
+
+        for element in [5, 1, 4, 2, 3, 9, 0] {
+            list.insert(element);
+            list.inner().check_invariants().unwrap();
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5, 9]);
+        assert_eq!(list.first(), Some(&0));
+        assert_eq!(list.last(), Some(&9));
+        assert_eq!(list.len(), 7);
+
+        assert!(list.contains(&4));
+        assert!(!list.contains(&6));
+        assert_eq!(list.remove(&4), Some(4));
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.remove(&4), None);
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.remove(&0), Some(0));
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.remove(&9), Some(9));
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.pop_first(), Some(1));
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.pop_last(), Some(5));
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn insert_is_stable() {
+        let mut list = SortedList::new();
+        for (key, tag) in [(2, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (2, 'e'), (1, 'f')] {
+            list.insert(event(key, tag));
+            list.inner().check_invariants().unwrap();
+        }
+
+        let tags: String = list.iter().map(|e| e.tag).collect();
+        assert_eq!(tags, "bfaced");
+    }
+
+    #[test]
+    fn merge() {
+        let mut list: SortedList<u32> = [1, 4, 4, 9].into_iter().collect();
+        let mut other: SortedList<u32> = [0, 2, 3, 4, 10, 11].into_iter().collect();
+        list.merge(&mut other);
+        list.inner().check_invariants().unwrap();
+        other.inner().check_invariants().unwrap();
+        assert!(other.is_empty());
+        assert_eq!(list.len(), 10);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 4, 4, 9, 10, 11]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![11, 10, 9, 4, 4, 4, 3, 2, 1, 0]);
+
+        // Check merging with empty lists on either side
+        list.merge(&mut other);
+        list.inner().check_invariants().unwrap();
+        assert_eq!(list.len(), 10);
+        other.merge(&mut list);
+        list.inner().check_invariants().unwrap();
+        other.inner().check_invariants().unwrap();
+        assert_eq!(other.len(), 10);
+        assert_eq!(other.first(), Some(&0));
+        assert_eq!(other.last(), Some(&11));
+
+        // The merged list keeps working as a normal list
+        other.insert(5);
+        other.inner().check_invariants().unwrap();
+        assert_eq!(other.pop_last(), Some(11));
+        other.inner().check_invariants().unwrap();
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 4, 4, 5, 9, 10]);
+    }
+
+    #[test]
+    fn merge_is_stable() {
+        let mut list: SortedList<Event> = [event(1, 'a'), event(2, 'b')].into_iter().collect();
+        let mut other: SortedList<Event> = [event(1, 'x'), event(2, 'y')].into_iter().collect();
+        list.merge(&mut other);
+        list.inner().check_invariants().unwrap();
+        other.inner().check_invariants().unwrap();
+        assert_eq!(list.iter().map(|e| e.tag).collect::<String>(), "axby");
+    }
+
+    #[test]
+    fn range() {
+        let list: SortedList<i32> = [7, 3, 5, 1, 9, 5].into_iter().collect();
+        list.inner().check_invariants().unwrap();
+        let collect = |iter: crate::sixth::Iter<'_, i32>| iter.copied().collect::<Vec<_>>();
+
+        assert_eq!(collect(list.range(3..7)), vec![3, 5, 5]);
+        assert_eq!(collect(list.range(2..=7)), vec![3, 5, 5, 7]);
+        assert_eq!(collect(list.range(6..)), vec![7, 9]);
+        assert_eq!(collect(list.range(..)), vec![1, 3, 5, 5, 7, 9]);
+        assert_eq!(list.range(3..=5).rev().copied().collect::<Vec<_>>(), vec![5, 5, 3]);
+        assert_eq!(list.range(4..7).len(), 2);
+
+        // Check ranges that match nothing
+        assert_eq!(collect(list.range(10..)), vec![]);
+        assert_eq!(collect(list.range(..0)), vec![]);
+        assert_eq!(collect(list.range(6..7)), vec![]);
+    }
+
+    #[test]
+    fn matches_sorted_vec() {
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let mut list = SortedList::new();
+        let mut model: Vec<u32> = Vec::new();
+        for _ in 0..2_000 {
+            let value = next() % 100;
+            if next() % 3 == 0 {
+                let expected = model.iter().position(|&v| v == value).map(|i| model.remove(i));
+                assert_eq!(list.remove(&value), expected);
+            } else {
+                list.insert(value);
+                let index = model.partition_point(|&v| v <= value);
+                model.insert(index, value);
+            }
+            list.inner().check_invariants().unwrap();
+        }
+        assert!(list.iter().eq(model.iter()));
+        assert!(list.range(20..40).eq(model.iter().filter(|v| (20..40).contains(*v))));
+    }
+}
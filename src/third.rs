@@ -1,14 +1,14 @@
-use std::rc::Rc;
+use alloc::rc::Rc;
 
 pub struct List<T> {
-    head: Link<T>
+    pub(crate) head: Link<T>
 }
 
-type Link<T> = Option<Rc<Node<T>>>;
+pub(crate) type Link<T> = Option<Rc<Node<T>>>;
 
 pub struct Node<T> {
-    element: T,
-    next: Link<T>
+    pub(crate) element: T,
+    pub(crate) next: Link<T>
 }
 
 impl<T> List<T> {
@@ -33,7 +33,7 @@ impl<T> List<T> {
         }
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter { next: self.head.as_deref() }
     }
 }
@@ -52,6 +52,19 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+// Another handle onto the same nodes, so it's O(1) and doesn't need `T: Clone`
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List { head: self.head.clone() }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut current_link = self.head.take();
@@ -65,6 +78,34 @@ impl<T> Drop for List<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::serde_support::deserialize_seq(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> crate::serde_support::FromSeq<'de> for List<T> {
+    fn from_seq<S: serde::de::SeqAccess<'de>>(mut seq: S) -> Result<Self, S::Error> {
+        let mut list = List::new();
+        // Nothing else holds these nodes yet, so each one can still be linked onto in place
+        let mut cursor = &mut list.head;
+        while let Some(element) = seq.next_element()? {
+            let node = cursor.insert(Rc::new(Node { element, next: None }));
+            cursor = &mut Rc::get_mut(node).unwrap().next;
+        }
+        Ok(list)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
@@ -105,6 +146,30 @@ mod test {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
-}
 
+    #[test]
+    fn clone_shares_nodes() {
+        let list = List::new().prepend(1).prepend(2);
+        let shared = list.clone();
+        let branch = shared.prepend(3);
+        assert!(branch.tail().iter().eq(list.iter()));
+        assert_eq!(std::rc::Rc::strong_count(list.head.as_ref().unwrap()), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::serde_support::json;
+
+        let list = List::new().prepend("c".to_string()).prepend("b\\\"".to_string()).prepend("a".to_string());
+        let text = json::to_string(&list).unwrap();
+        assert_eq!(text, r#"["a","b\\\"","c"]"#);
 
+        let decoded: List<String> = json::from_str(&text).unwrap();
+        assert!(decoded.iter().eq(list.iter()));
+
+        // A decoded list shares structure like any other
+        let shared = decoded.tail();
+        assert_eq!(json::to_string(&shared).unwrap(), r#"["b\\\"","c"]"#);
+    }
+}
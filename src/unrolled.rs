@@ -0,0 +1,691 @@
+//! An unrolled deque: `sixth::LinkedList`'s API, but each node holds up to `N` elements in a
+//! small inline array, so iteration walks mostly contiguous memory instead of chasing one
+//! pointer per element.
+//!
+//! A node's elements occupy a contiguous run `start..start + len` of its array. Pushing at
+//! either end fills the end node's spare room before allocating a new one. Inserting into a
+//! full node splits it in half, and removing from a node that drops below a quarter full
+//! merges it with a neighbour when the two fit in one node.
+
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+
+pub struct UnrolledList<T, const N: usize = 16> {
+    head: Link<T, N>,
+    tail: Link<T, N>,
+    length: usize,
+    _boo: PhantomData<T>
+}
+
+type Link<T, const N: usize> = Option<NonNull<Node<T, N>>>;
+
+struct Node<T, const N: usize> {
+    previous: Link<T, N>,
+    next: Link<T, N>,
+    start: usize,
+    len: usize,
+    elements: [MaybeUninit<T>; N]
+}
+
+unsafe impl<T: Send, const N: usize> Send for UnrolledList<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for UnrolledList<T, N> {}
+
+impl<T, const N: usize> Node<T, N> {
+    fn allocate(start: usize) -> NonNull<Node<T, N>> {
+        let node = Box::new(Node {
+            previous: None,
+            next: None,
+            start,
+            len: 0,
+            elements: [const { MaybeUninit::uninit() }; N]
+        });
+        unsafe { NonNull::new_unchecked(Box::into_raw(node)) }
+    }
+
+    // Raw rather than indexed so that one-past-the-end is a valid position to shift from
+    fn slot(&mut self, position: usize) -> *mut T {
+        debug_assert!(position <= N);
+        unsafe { self.elements.as_mut_ptr().cast::<T>().add(position) }
+    }
+
+    fn get(&self, offset: usize) -> &T {
+        debug_assert!(offset < self.len);
+        unsafe { self.elements[self.start + offset].assume_init_ref() }
+    }
+
+    fn get_mut(&mut self, offset: usize) -> &mut T {
+        debug_assert!(offset < self.len);
+        unsafe { self.elements[self.start + offset].assume_init_mut() }
+    }
+
+    fn has_room_after(&self) -> bool {
+        self.start + self.len < N
+    }
+
+    fn has_room_before(&self) -> bool {
+        self.start > 0
+    }
+
+    // Shifts `count` elements starting at array position `from` by one slot either way
+    unsafe fn shift(&mut self, from: usize, count: usize, right: bool) {
+        let source = self.slot(from);
+        let destination = if right { source.add(1) } else { source.sub(1) };
+        ptr::copy(source, destination, count);
+    }
+
+    fn insert(&mut self, offset: usize, element: T) {
+        debug_assert!(self.len < N && offset <= self.len);
+        unsafe {
+            if self.has_room_after() {
+                self.shift(self.start + offset, self.len - offset, true);
+            } else {
+                self.shift(self.start, offset, false);
+                self.start -= 1;
+            }
+            self.slot(self.start + offset).write(element);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, offset: usize) -> T {
+        debug_assert!(offset < self.len);
+        unsafe {
+            let element = self.slot(self.start + offset).read();
+            // Close the gap from whichever side has fewer elements to move
+            if offset < self.len / 2 {
+                self.shift(self.start, offset, true);
+                self.start += 1;
+            } else {
+                self.shift(self.start + offset + 1, self.len - offset - 1, false);
+            }
+            self.len -= 1;
+            element
+        }
+    }
+
+    // Moves the elements so they start at the front of the array, leaving all spare room after
+    fn pack_front(&mut self) {
+        if self.start != 0 {
+            unsafe { ptr::copy(self.slot(self.start), self.slot(0), self.len); }
+            self.start = 0;
+        }
+    }
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    pub fn new() -> Self {
+        const { assert!(N >= 2, "an unrolled node needs room for at least two elements") };
+        UnrolledList { head: None, tail: None, length: 0, _boo: PhantomData }
+    }
+
+    pub fn push_front(&mut self, element: T) {
+        unsafe {
+            let head = match self.head {
+                Some(head) if (*head.as_ptr()).has_room_before() => head,
+                _ => {
+                    let new_head = Node::allocate(N);
+                    self.link_after(None, new_head);
+                    new_head
+                }
+            };
+            (*head.as_ptr()).insert(0, element);
+        }
+        self.length += 1;
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        unsafe {
+            let tail = match self.tail {
+                Some(tail) if (*tail.as_ptr()).has_room_after() => tail,
+                _ => {
+                    let new_tail = Node::allocate(0);
+                    self.link_after(self.tail, new_tail);
+                    new_tail
+                }
+            };
+            let tail = &mut *tail.as_ptr();
+            tail.insert(tail.len, element);
+        }
+        self.length += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.map(|head| unsafe { self.remove_from(head, 0, false) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.map(|tail| unsafe {
+            let offset = (*tail.as_ptr()).len - 1;
+            self.remove_from(tail, offset, false)
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.map(|head| unsafe { (*head.as_ptr()).get(0) })
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|head| unsafe { (*head.as_ptr()).get_mut(0) })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.map(|tail| unsafe {
+            let tail = &*tail.as_ptr();
+            tail.get(tail.len - 1)
+        })
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.tail.map(|tail| unsafe {
+            let tail = &mut *tail.as_ptr();
+            tail.get_mut(tail.len - 1)
+        })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.locate(index).map(|(node, offset)| unsafe { (*node.as_ptr()).get(offset) })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.locate(index).map(|(node, offset)| unsafe { (*node.as_ptr()).get_mut(offset) })
+    }
+
+    /// Inserts `element` so that it ends up at `index`, splitting the node it lands in if
+    /// that node is full.
+    ///
+    /// # Panics
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(index <= self.length, "insertion index {} is out of bounds for length {}", index, self.length);
+        if index == 0 {
+            return self.push_front(element);
+        }
+        if index == self.length {
+            return self.push_back(element);
+        }
+
+        let (node, offset) = self.locate(index).unwrap();
+        unsafe {
+            let (node, offset) = if (*node.as_ptr()).len == N {
+                let upper = self.split(node);
+                if offset > N / 2 { (upper, offset - N / 2) } else { (node, offset) }
+            } else {
+                (node, offset)
+            };
+            (*node.as_ptr()).insert(offset, element);
+        }
+        self.length += 1;
+    }
+
+    /// Removes the element at `index`, merging its node into a neighbour if it gets sparse.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.locate(index).map(|(node, offset)| unsafe { self.remove_from(node, offset, true) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            front: self.head,
+            front_offset: 0,
+            back: self.tail,
+            back_offset: self.tail.map_or(0, |tail| unsafe { (*tail.as_ptr()).len }),
+            length: self.length,
+            _boo: PhantomData
+        }
+    }
+
+    // Finds the node holding `index` and its offset within that node, walking in from
+    // whichever end is nearer.
+    fn locate(&self, index: usize) -> Option<(NonNull<Node<T, N>>, usize)> {
+        if index >= self.length {
+            return None;
+        }
+        unsafe {
+            if index < self.length / 2 {
+                let mut node = self.head.unwrap();
+                let mut offset = index;
+                while offset >= (*node.as_ptr()).len {
+                    offset -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).next.unwrap();
+                }
+                Some((node, offset))
+            } else {
+                let mut node = self.tail.unwrap();
+                let mut from_back = self.length - 1 - index;
+                while from_back >= (*node.as_ptr()).len {
+                    from_back -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).previous.unwrap();
+                }
+                Some((node, (*node.as_ptr()).len - 1 - from_back))
+            }
+        }
+    }
+
+    // Links a fresh node in after `previous`, or at the front if `previous` is `None`
+    unsafe fn link_after(&mut self, previous: Link<T, N>, node: NonNull<Node<T, N>>) {
+        let next = match previous {
+            Some(previous) => (*previous.as_ptr()).next,
+            None => self.head
+        };
+        (*node.as_ptr()).previous = previous;
+        (*node.as_ptr()).next = next;
+        match previous {
+            Some(previous) => (*previous.as_ptr()).next = Some(node),
+            None => self.head = Some(node)
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).previous = Some(node),
+            None => self.tail = Some(node)
+        }
+    }
+
+    unsafe fn unlink_and_free(&mut self, node: NonNull<Node<T, N>>) {
+        let boxed = Box::from_raw(node.as_ptr());
+        match boxed.previous {
+            Some(previous) => (*previous.as_ptr()).next = boxed.next,
+            None => self.head = boxed.next
+        }
+        match boxed.next {
+            Some(next) => (*next.as_ptr()).previous = boxed.previous,
+            None => self.tail = boxed.previous
+        }
+        // `elements` is all `MaybeUninit`, so dropping the box never drops an element
+    }
+
+    // Moves the upper half of a full node into a new node linked right after it
+    unsafe fn split(&mut self, node: NonNull<Node<T, N>>) -> NonNull<Node<T, N>> {
+        let upper = Node::allocate(0);
+        self.link_after(Some(node), upper);
+
+        let lower = &mut *node.as_ptr();
+        let moved = lower.len - N / 2;
+        ptr::copy_nonoverlapping(lower.slot(lower.start + N / 2), (*upper.as_ptr()).slot(0), moved);
+        (*upper.as_ptr()).len = moved;
+        lower.len = N / 2;
+        upper
+    }
+
+    // Appends every element of `right` onto `left` and frees `right`; the caller checks they fit
+    unsafe fn merge(&mut self, left: NonNull<Node<T, N>>, right: NonNull<Node<T, N>>) {
+        let left_node = &mut *left.as_ptr();
+        let right_node = &mut *right.as_ptr();
+        debug_assert!(left_node.len + right_node.len <= N);
+
+        if left_node.start + left_node.len + right_node.len > N {
+            left_node.pack_front();
+        }
+        let destination = left_node.slot(left_node.start + left_node.len);
+        ptr::copy_nonoverlapping(right_node.slot(right_node.start), destination, right_node.len);
+        left_node.len += right_node.len;
+        right_node.len = 0;
+        self.unlink_and_free(right);
+    }
+
+    unsafe fn remove_from(&mut self, node: NonNull<Node<T, N>>, offset: usize, rebalance: bool) -> T {
+        let element = (*node.as_ptr()).remove(offset);
+        self.length -= 1;
+
+        let len = (*node.as_ptr()).len;
+        if len == 0 {
+            self.unlink_and_free(node);
+        } else if rebalance && len < N / 4 {
+            let previous = (*node.as_ptr()).previous;
+            let next = (*node.as_ptr()).next;
+            if let Some(next) = next.filter(|next| len + (*next.as_ptr()).len <= N) {
+                self.merge(node, next);
+            } else if let Some(previous) = previous.filter(|previous| len + (*previous.as_ptr()).len <= N) {
+                self.merge(previous, node);
+            }
+        }
+        element
+    }
+}
+
+impl<T, const N: usize> Default for UnrolledList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for UnrolledList<T, N> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a UnrolledList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T, const N: usize = 16> {
+    front: Link<T, N>,
+    // Offset of the next element `next` will yield within `front`
+    front_offset: usize,
+    back: Link<T, N>,
+    // One past the offset of the next element `next_back` will yield within `back`
+    back_offset: usize,
+    length: usize,
+    _boo: PhantomData<&'a T>
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.front.map(|front_ptr| {
+                unsafe {
+                    let front = &*front_ptr.as_ptr();
+                    let element = front.get(self.front_offset);
+                    self.front_offset += 1;
+                    if self.front_offset == front.len {
+                        self.front = front.next;
+                        self.front_offset = 0;
+                    }
+                    self.length -= 1;
+                    element
+                }
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.length >= 1 {
+            self.back.map(|back_ptr| {
+                unsafe {
+                    let back = &*back_ptr.as_ptr();
+                    self.back_offset -= 1;
+                    let element = back.get(self.back_offset);
+                    if self.back_offset == 0 {
+                        self.back = back.previous;
+                        self.back_offset = back.previous.map_or(0, |previous| (*previous.as_ptr()).len);
+                    }
+                    self.length -= 1;
+                    element
+                }
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {
+    fn len(&self) -> usize {
+       self.length
+    }
+}
+
+pub struct IntoIter<T, const N: usize = 16> {
+    list: UnrolledList<T, N>
+}
+
+impl<T, const N: usize> IntoIterator for UnrolledList<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+       self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.length, Some(self.list.length))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use super::UnrolledList;
+
+    // Lengths of each node from front to back
+    fn node_lens<T, const N: usize>(list: &UnrolledList<T, N>) -> Vec<usize> {
+        let mut lens = Vec::new();
+        let mut current = list.head;
+        while let Some(node) = current {
+            unsafe {
+                lens.push((*node.as_ptr()).len);
+                current = (*node.as_ptr()).next;
+            }
+        }
+        lens
+    }
+
+    #[test]
+    fn basics() {
+        let mut list: UnrolledList<i32> = UnrolledList::new();
+        list.push_front(3);
+        list.push_front(6);
+        list.push_front(2);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_back(), Some(6));
+        list.push_front(4);
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut list: UnrolledList<i32, 2> = UnrolledList::new();
+        assert_eq!(list.front(), None);
+        assert_eq!(list.back_mut(), None);
+
+        list.push_front(3);
+        list.push_front(2);
+        list.push_back(4);
+        list.push_back(5);
+        list.push_front(1);
+        list.push_back(6);
+
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&6));
+        *list.front_mut().unwrap() = -1;
+        *list.back_mut().unwrap() = -6;
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![-1, 2, 3, 4, 5, -6]);
+        assert_eq!(list.len(), 6);
+    }
+
+    #[test]
+    fn fills_nodes_from_both_ends() {
+        let mut list: UnrolledList<i32, 4> = UnrolledList::new();
+        for i in 0..10 {
+            list.push_back(i);
+        }
+        assert_eq!(node_lens(&list), vec![4, 4, 2]);
+
+        for i in 1..=5 {
+            list.push_front(-i);
+        }
+        assert_eq!(node_lens(&list), vec![1, 4, 4, 4, 2]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (-5..10).collect::<Vec<_>>());
+
+        // Emptied end nodes are freed as soon as they run dry
+        list.pop_front();
+        assert_eq!(node_lens(&list), vec![4, 4, 4, 2]);
+        list.pop_back();
+        list.pop_back();
+        assert_eq!(node_lens(&list), vec![4, 4, 4]);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list: UnrolledList<i32, 3> = UnrolledList::new();
+        for i in 1..=7 {
+            list.push_back(i);
+        }
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&7));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list: UnrolledList<i32> = UnrolledList::new();
+        list.push_back(5);
+        list.push_back(10);
+        list.push_back(3);
+        list.push_back(8);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.next(), Some(10));
+        assert_eq!(iter.next_back(), Some(8));
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn insert_splits_full_nodes() {
+        let mut list: UnrolledList<i32, 4> = UnrolledList::new();
+        for i in [0, 1, 2, 4] {
+            list.push_back(i);
+        }
+        assert_eq!(node_lens(&list), vec![4]);
+
+        list.insert(3, 3);
+        assert_eq!(node_lens(&list), vec![2, 3]);
+        list.insert(1, 10);
+        assert_eq!(node_lens(&list), vec![3, 3]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 10, 1, 2, 3, 4]);
+
+        assert_eq!(list.get(3), Some(&2));
+        *list.get_mut(3).unwrap() = 20;
+        assert_eq!(list.get(3), Some(&20));
+        assert_eq!(list.get(6), None);
+    }
+
+    #[test]
+    fn remove_merges_sparse_nodes() {
+        let mut list: UnrolledList<i32, 8> = UnrolledList::new();
+        for i in 0..16 {
+            list.push_back(i);
+        }
+        assert_eq!(node_lens(&list), vec![8, 8]);
+
+        for _ in 0..5 {
+            list.remove(1);
+        }
+        assert_eq!(node_lens(&list), vec![3, 8]);
+
+        // Dropping below a quarter full isn't enough on its own; the neighbour has to fit too
+        list.remove(1);
+        assert_eq!(node_lens(&list), vec![2, 8]);
+
+        list.remove(2);
+        list.remove(2);
+        assert_eq!(node_lens(&list), vec![2, 6]);
+
+        list.remove(0);
+        assert_eq!(node_lens(&list), vec![7]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![7, 10, 11, 12, 13, 14, 15]);
+        assert_eq!(list.remove(7), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut list: UnrolledList<i32> = UnrolledList::new();
+        list.insert(1, 0);
+    }
+
+    #[test]
+    fn matches_vec_model() {
+        // A small xorshift so the sequence of operations is the same on every run
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize
+        };
+
+        let mut list: UnrolledList<usize, 4> = UnrolledList::new();
+        let mut model = Vec::new();
+        for step in 0..5_000 {
+            match next() % 6 {
+                0 => { list.push_front(step); model.insert(0, step); }
+                1 => { list.push_back(step); model.push(step); }
+                2 => assert_eq!(list.pop_front(), if model.is_empty() { None } else { Some(model.remove(0)) }),
+                3 => assert_eq!(list.pop_back(), model.pop()),
+                4 => {
+                    let index = next() % (model.len() + 1);
+                    list.insert(index, step);
+                    model.insert(index, step);
+                }
+                _ => {
+                    let index = next() % (model.len() + 1);
+                    let expected = if index < model.len() { Some(model.remove(index)) } else { None };
+                    assert_eq!(list.remove(index), expected);
+                }
+            }
+            assert_eq!(list.len(), model.len());
+            assert_eq!(list.front(), model.first());
+            assert_eq!(list.back(), model.last());
+        }
+        assert!(list.iter().eq(model.iter()));
+        assert!(list.iter().rev().eq(model.iter().rev()));
+        assert!(node_lens(&list).iter().all(|&len| (1..=4).contains(&len)));
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let tracker = Rc::new(());
+        {
+            let mut list: UnrolledList<Rc<()>, 4> = UnrolledList::new();
+            for _ in 0..10 {
+                list.push_back(tracker.clone());
+                list.push_front(tracker.clone());
+            }
+            list.remove(7);
+            list.insert(3, tracker.clone());
+            assert_eq!(Rc::strong_count(&tracker), 21);
+        }
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+}
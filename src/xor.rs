@@ -0,0 +1,436 @@
+//! An experimental deque whose nodes keep a single link word, `previous ^ next`, instead of
+//! two pointers, saving one word per element over `sixth::LinkedList`.
+//!
+//! Walking the list needs the address of the node you came from to recover the next one, so
+//! iteration always starts from an end. The same symmetry makes `reverse` O(1): swapping the
+//! head and tail pointers is all it takes.
+//!
+//! An XORed address carries no provenance, so `address` exposes each node's address the first
+//! time it's XORed into a link, and pointers are rebuilt with `ptr::with_exposed_provenance_mut`.
+//! That's the model Miri checks under its default permissive provenance.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+use crate::allocator::{self, Allocator, Global};
+
+pub struct XorDeque<T, A: Allocator = Global> {
+    head: *mut Node<T>,
+    tail: *mut Node<T>,
+    length: usize,
+    allocator: A,
+    _boo: PhantomData<T>
+}
+
+struct Node<T> {
+    // The exposed addresses of the previous and next nodes XORed together, with null as 0
+    link: usize,
+    element: T
+}
+
+// Nodes are only reached through `&self` or `&mut self` on the deque, never shared outside it
+unsafe impl<T: Send, A: Allocator + Send> Send for XorDeque<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for XorDeque<T, A> {}
+
+fn address<T>(node: *mut Node<T>) -> usize {
+    node.expose_provenance()
+}
+
+fn node_at<T>(address: usize) -> *mut Node<T> {
+    ptr::with_exposed_provenance_mut(address)
+}
+
+// The neighbour of `node` on the side away from `from`
+unsafe fn step<T>(from: *mut Node<T>, node: *mut Node<T>) -> *mut Node<T> {
+    node_at((*node).link ^ address(from))
+}
+
+impl<T> XorDeque<T> {
+    pub fn new() -> Self {
+        XorDeque::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> XorDeque<T, A> {
+    /// Creates a deque whose nodes are allocated and freed through `allocator`.
+    pub fn new_in(allocator: A) -> Self {
+        XorDeque { head: ptr::null_mut(), tail: ptr::null_mut(), length: 0, allocator, _boo: PhantomData }
+    }
+
+    pub fn push_front(&mut self, element: T) {
+        let node = self.allocate(element, self.head);
+        if self.head.is_null() {
+            self.tail = node;
+        } else {
+            unsafe { (*self.head).link ^= address(node); }
+        }
+        self.head = node;
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        let node = self.allocate(element, self.tail);
+        if self.tail.is_null() {
+            self.head = node;
+        } else {
+            unsafe { (*self.tail).link ^= address(node); }
+        }
+        self.tail = node;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head;
+        if head.is_null() {
+            return None;
+        }
+        unsafe {
+            self.head = step(ptr::null_mut(), head);
+            if self.head.is_null() {
+                self.tail = ptr::null_mut();
+            } else {
+                (*self.head).link ^= address(head);
+            }
+            Some(self.free(head))
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail;
+        if tail.is_null() {
+            return None;
+        }
+        unsafe {
+            self.tail = step(ptr::null_mut(), tail);
+            if self.tail.is_null() {
+                self.head = ptr::null_mut();
+            } else {
+                (*self.tail).link ^= address(tail);
+            }
+            Some(self.free(tail))
+        }
+    }
+
+    /// Reverses the deque in O(1). Every link is symmetric, so only the ends need swapping.
+    pub fn reverse(&mut self) {
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.head.as_ref().map(|node| &node.element) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.head.as_mut().map(|node| &mut node.element) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.tail.as_ref().map(|node| &node.element) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.tail.as_mut().map(|node| &mut node.element) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head,
+            before_front: ptr::null_mut(),
+            back: self.tail,
+            after_back: ptr::null_mut(),
+            length: self.length,
+            _boo: PhantomData
+        }
+    }
+
+    // Allocates a node for a new end whose only neighbour is `neighbour`
+    fn allocate(&mut self, element: T, neighbour: *mut Node<T>) -> *mut Node<T> {
+        let node = allocator::allocate_node(&self.allocator, Node { link: address(neighbour), element });
+        self.length += 1;
+        node.as_ptr()
+    }
+
+    unsafe fn free(&mut self, node: *mut Node<T>) -> T {
+        let element = ptr::addr_of!((*node).element).read();
+        allocator::deallocate_node(&self.allocator, NonNull::new_unchecked(node));
+        self.length -= 1;
+        element
+    }
+}
+
+impl<T, A: Allocator + Default> Default for XorDeque<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T, A: Allocator> Drop for XorDeque<T, A> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for XorDeque<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for XorDeque<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for XorDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = XorDeque::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: *mut Node<T>,
+    before_front: *mut Node<T>,
+    back: *mut Node<T>,
+    after_back: *mut Node<T>,
+    length: usize,
+    _boo: PhantomData<&'a T>
+}
+
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.length == 0 {
+            return None;
+        }
+        unsafe {
+            let node = self.front;
+            self.front = step(self.before_front, node);
+            self.before_front = node;
+            self.length -= 1;
+            Some(&(*node).element)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.length == 0 {
+            return None;
+        }
+        unsafe {
+            let node = self.back;
+            self.back = step(self.after_back, node);
+            self.after_back = node;
+            self.length -= 1;
+            Some(&(*node).element)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {
+    fn len(&self) -> usize {
+        self.length
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a XorDeque<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T, A: Allocator = Global>(XorDeque<T, A>);
+
+impl<T, A: Allocator> IntoIterator for XorDeque<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::mem::size_of;
+    use std::rc::Rc;
+    use crate::allocator::CountingAllocator;
+    use crate::sixth::{self, LinkedList};
+    use super::{Node, XorDeque};
+
+    #[test]
+    fn basics() {
+        let mut deque = XorDeque::new();
+
+        // Check empty deque behaves right
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.front(), None);
+
+        deque.push_back(2);
+        deque.push_front(1);
+        deque.push_back(3);
+        assert_eq!(deque.len(), 3);
+        assert_eq!((deque.front(), deque.back()), (Some(&1), Some(&3)));
+        *deque.back_mut().unwrap() = 30;
+
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(30));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_front(), None);
+        assert!(deque.is_empty());
+
+        // Push some more just to make sure nothing's corrupted
+        deque.push_front(4);
+        assert_eq!(deque.pop_back(), Some(4));
+    }
+
+    #[test]
+    fn iteration_both_ways() {
+        let deque: XorDeque<i32> = (0..6).collect();
+        assert!(deque.iter().eq([0, 1, 2, 3, 4, 5].iter()));
+        assert!(deque.iter().rev().eq([5, 4, 3, 2, 1, 0].iter()));
+
+        // Meeting in the middle
+        let mut iter = deque.iter();
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(format!("{:?}", deque), "[0, 1, 2, 3, 4, 5]");
+        assert_eq!(deque.into_iter().rev().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut deque: XorDeque<i32> = (1..=4).collect();
+        deque.reverse();
+        assert!(deque.iter().eq([4, 3, 2, 1].iter()));
+
+        // Pushes and pops keep working on the reversed links
+        deque.push_front(5);
+        deque.push_back(0);
+        assert_eq!(deque.pop_front(), Some(5));
+        deque.reverse();
+        assert!(deque.iter().eq([0, 1, 2, 3, 4].iter()));
+
+        let mut single: XorDeque<i32> = [7].into_iter().collect();
+        single.reverse();
+        assert_eq!(single.pop_back(), Some(7));
+        XorDeque::<i32>::new().reverse();
+    }
+
+    #[test]
+    fn matches_vecdeque() {
+        let mut deque = XorDeque::new();
+        let mut model = VecDeque::new();
+        let mut state = 0x2545_f491_u32;
+        for i in 0..5_000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            match state % 7 {
+                0 | 1 => { deque.push_front(i); model.push_front(i); }
+                2 | 3 => { deque.push_back(i); model.push_back(i); }
+                4 => assert_eq!(deque.pop_front(), model.pop_front()),
+                5 => assert_eq!(deque.pop_back(), model.pop_back()),
+                _ => {
+                    deque.reverse();
+                    model.make_contiguous().reverse();
+                }
+            }
+            assert_eq!(deque.len(), model.len());
+        }
+        assert!(deque.iter().eq(model.iter()));
+        assert!(deque.iter().rev().eq(model.iter().rev()));
+    }
+
+    #[test]
+    fn drops_every_element() {
+        let tracker = Rc::new(());
+        let mut deque = XorDeque::new();
+        for _ in 0..10 {
+            deque.push_back(tracker.clone());
+            deque.push_front(tracker.clone());
+        }
+        deque.pop_back();
+        assert_eq!(Rc::strong_count(&tracker), 20);
+        drop(deque);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn uses_less_memory_than_sixth() {
+        let (xor_allocator, sixth_allocator) = (CountingAllocator::new(), CountingAllocator::new());
+        let mut deque = XorDeque::new_in(&xor_allocator);
+        let mut list = LinkedList::new_in(&sixth_allocator);
+        for i in 0..1_000_usize {
+            deque.push_back(i);
+            list.push_back(i);
+        }
+
+        // A word-sized element keeps padding out of it on every target: a link word plus the
+        // element, against two pointers plus the element
+        let word = size_of::<usize>();
+        assert_eq!(size_of::<Node<usize>>(), 2 * word);
+        assert_eq!(size_of::<sixth::Node<usize>>(), 3 * word);
+        assert_eq!(xor_allocator.live_bytes(), 1_000 * size_of::<Node<usize>>());
+        assert_eq!(sixth_allocator.live_bytes(), 1_000 * size_of::<sixth::Node<usize>>());
+
+        drop(deque);
+        assert_eq!(xor_allocator.live(), 0);
+    }
+}
@@ -0,0 +1,15 @@
+//! Builds the library with the `std` feature off, so anything that sneaks a `std` dependency
+//! into the `no_std` + `alloc` part of the crate fails here rather than on an embedded target.
+
+use std::process::Command;
+
+#[test]
+fn builds_without_std() {
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--no-default-features", "--features", "debug-invariants"])
+        .args(["--manifest-path", concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")])
+        .args(["--target-dir", concat!(env!("CARGO_TARGET_TMPDIR"), "/no_std")])
+        .output()
+        .expect("failed to run cargo");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}